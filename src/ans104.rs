@@ -0,0 +1,620 @@
+use super::*;
+use crate::base64::{decode_url, encode_url};
+use crate::crypto::{keccak256, sha256, sha384, verify_ed25519, verify_rsa_pss, verify_secp256k1};
+use crate::message::Message;
+use alloc::vec;
+use core::fmt;
+
+pub use crate::message::Tag;
+
+const VERSION: &str = "0.0.1";
+
+/// The most tags an ANS-104 data item may carry.
+pub const MAX_TAGS: usize = 128;
+/// The longest tag name an ANS-104 data item may carry, in bytes.
+pub const MAX_TAG_NAME_BYTES: usize = 1024;
+/// The longest tag value an ANS-104 data item may carry, in bytes.
+pub const MAX_TAG_VALUE_BYTES: usize = 3072;
+
+/// Registers the `ans104` module with Lua, exporting `encode` and `decode` between
+/// outbox messages and unsigned ANS-104 data items.
+///
+/// `encode(message, options)` serializes a message's `Target`, `Anchor`, `Tags` and
+/// `Data`. The options table may give:
+/// - `signatureType`: the signature scheme code, `1` (Arweave, default), `2` (Ed25519),
+///   `3` (Ethereum) or `4` (Solana).
+/// - `owner`, `signature`: raw bytes of the scheme's length. Both default to zeros.
+///
+/// `decode(bytes)` returns `{ SignatureType, Signature, Owner, Target, Anchor, Tags, Data }`
+/// with `Target` in base64url, as messages carry it.
+///
+/// `signatureData(message, options)` returns the deep hash a signer signs for the
+/// message, `id(bytes)` the base64url ID of an encoded item, `verify(bytes)` whether
+/// an encoded item's signature holds, and `deepHash(value)` the Arweave deep hash of a
+/// string or of a nested list of strings.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "ans104"))]
+pub fn ans104(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set("encode", lua.create_function(encode)?)?;
+    exports.set("decode", lua.create_function(decode)?)?;
+    exports.set("signatureData", lua.create_function(signature_data)?)?;
+    exports.set("id", lua.create_function(id)?)?;
+    exports.set("verify", lua.create_function(verify)?)?;
+    exports.set("deepHash", lua.create_function(deep_hash_value)?)?;
+    Ok(exports)
+}
+
+/// The signature schemes a data item can declare, by their ANS-104 code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureType {
+    Arweave = 1,
+    Ed25519 = 2,
+    Ethereum = 3,
+    Solana = 4,
+}
+
+impl SignatureType {
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(SignatureType::Arweave),
+            2 => Some(SignatureType::Ed25519),
+            3 => Some(SignatureType::Ethereum),
+            4 => Some(SignatureType::Solana),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+
+    /// The length of the scheme's signature, in bytes.
+    pub fn signature_len(self) -> usize {
+        match self {
+            SignatureType::Arweave => 512,
+            SignatureType::Ed25519 | SignatureType::Solana => 64,
+            SignatureType::Ethereum => 65,
+        }
+    }
+
+    /// The length of the scheme's owner public key, in bytes.
+    pub fn owner_len(self) -> usize {
+        match self {
+            SignatureType::Arweave => 512,
+            SignatureType::Ed25519 | SignatureType::Solana => 32,
+            SignatureType::Ethereum => 65,
+        }
+    }
+}
+
+/// Reasons a data item can't be encoded or decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DataItemError {
+    /// A signature type code outside `SignatureType`.
+    UnknownSignatureType(u16),
+    /// A field whose length doesn't match its scheme: field, expected, actual.
+    FieldLength(&'static str, usize, usize),
+    /// More than `MAX_TAGS` tags.
+    TooManyTags(usize),
+    /// A tag name or value over its byte limit: what, length.
+    TagTooLong(&'static str, usize),
+    /// The input ends inside the named field.
+    Truncated(&'static str),
+    /// A presence byte other than 0 or 1: field, byte.
+    InvalidPresence(&'static str, u8),
+    /// The Avro-encoded tags are malformed.
+    InvalidTags(&'static str),
+}
+
+impl fmt::Display for DataItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataItemError::UnknownSignatureType(code) => {
+                write!(f, "unknown signature type {}", code)
+            }
+            DataItemError::FieldLength(field, expected, actual) => {
+                write!(f, "{} must be {} bytes, got {}", field, expected, actual)
+            }
+            DataItemError::TooManyTags(count) => {
+                write!(f, "{} tags, at most {} allowed", count, MAX_TAGS)
+            }
+            DataItemError::TagTooLong(what, len) => {
+                let max = match *what {
+                    "name" => MAX_TAG_NAME_BYTES,
+                    _ => MAX_TAG_VALUE_BYTES,
+                };
+                write!(f, "tag {} is {} bytes, at most {} allowed", what, len, max)
+            }
+            DataItemError::Truncated(field) => write!(f, "data item ends inside {}", field),
+            DataItemError::InvalidPresence(field, byte) => {
+                write!(f, "invalid {} presence byte {}", field, byte)
+            }
+            DataItemError::InvalidTags(reason) => write!(f, "invalid tags: {}", reason),
+        }
+    }
+}
+
+impl From<DataItemError> for LuaError {
+    fn from(err: DataItemError) -> Self {
+        LuaError::RuntimeError(err.to_string())
+    }
+}
+
+/// An ANS-104 data item. Built with `new`, its signature and owner are zeroed, which is
+/// the layout a signer fills in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataItem {
+    pub signature_type: SignatureType,
+    pub signature: Vec<u8>,
+    pub owner: Vec<u8>,
+    pub target: Option<[u8; 32]>,
+    pub anchor: Option<[u8; 32]>,
+    pub tags: Vec<Tag>,
+    pub data: Vec<u8>,
+}
+
+impl DataItem {
+    /// An unsigned item with no target, anchor, tags or data.
+    pub fn new(signature_type: SignatureType) -> Self {
+        DataItem {
+            signature_type,
+            signature: vec![0; signature_type.signature_len()],
+            owner: vec![0; signature_type.owner_len()],
+            target: None,
+            anchor: None,
+            tags: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Serializes the item: signature type, signature, owner, target, anchor, tag
+    /// count and byte length, Avro tags, then the data.
+    pub fn encode(&self) -> Result<Vec<u8>, DataItemError> {
+        let kind = self.signature_type;
+        check_length("signature", kind.signature_len(), self.signature.len())?;
+        check_length("owner", kind.owner_len(), self.owner.len())?;
+        let tags = encode_tags(&self.tags)?;
+
+        let mut out = Vec::with_capacity(
+            2 + self.signature.len() + self.owner.len() + 66 + 16 + tags.len() + self.data.len(),
+        );
+        out.extend_from_slice(&kind.code().to_le_bytes());
+        out.extend_from_slice(&self.signature);
+        out.extend_from_slice(&self.owner);
+        for field in [&self.target, &self.anchor] {
+            match field {
+                Some(bytes) => {
+                    out.push(1);
+                    out.extend_from_slice(bytes);
+                }
+                None => out.push(0),
+            }
+        }
+        out.extend_from_slice(&(self.tags.len() as u64).to_le_bytes());
+        out.extend_from_slice(&(tags.len() as u64).to_le_bytes());
+        out.extend_from_slice(&tags);
+        out.extend_from_slice(&self.data);
+        Ok(out)
+    }
+
+    /// Parses a serialized item; everything after the tags is its data.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DataItemError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let code = u16::from_le_bytes(reader.array("signature type")?);
+        let signature_type =
+            SignatureType::from_code(code).ok_or(DataItemError::UnknownSignatureType(code))?;
+        let signature = reader
+            .take(signature_type.signature_len(), "signature")?
+            .to_vec();
+        let owner = reader.take(signature_type.owner_len(), "owner")?.to_vec();
+        let target = reader.optional("target")?;
+        let anchor = reader.optional("anchor")?;
+        let count = u64::from_le_bytes(reader.array("tag count")?);
+        let length = u64::from_le_bytes(reader.array("tag length")?);
+        let length = usize::try_from(length).map_err(|_| DataItemError::Truncated("tags"))?;
+        let tags = decode_tags(reader.take(length, "tags")?)?;
+        if tags.len() as u64 != count {
+            return Err(DataItemError::InvalidTags(
+                "tag count doesn't match the header",
+            ));
+        }
+        Ok(DataItem {
+            signature_type,
+            signature,
+            owner,
+            target,
+            anchor,
+            tags,
+            data: bytes[reader.offset..].to_vec(),
+        })
+    }
+
+    /// The deep hash of the fields a signature covers, which is what gets signed.
+    pub fn signature_data(&self) -> Result<[u8; 48], DataItemError> {
+        fn blob(bytes: &[u8]) -> DeepHashChunk {
+            DeepHashChunk::Blob(bytes.to_vec())
+        }
+        // An absent target or anchor hashes as an empty blob
+        fn optional(field: &Option<[u8; 32]>) -> DeepHashChunk {
+            blob(field.as_ref().map_or(&[][..], |bytes| &bytes[..]))
+        }
+        Ok(deep_hash(&DeepHashChunk::List(vec![
+            blob(b"dataitem"),
+            blob(b"1"),
+            blob(self.signature_type.code().to_string().as_bytes()),
+            blob(&self.owner),
+            optional(&self.target),
+            optional(&self.anchor),
+            blob(&encode_tags(&self.tags)?),
+            blob(&self.data),
+        ])))
+    }
+
+    /// Checks the signature over `signature_data` against the owner's public key: RSA-PSS
+    /// for Arweave, Ed25519 for Ed25519 and Solana, and for Ethereum ECDSA over
+    /// secp256k1 of the data as an Ethereum signed message.
+    pub fn verify(&self) -> Result<bool, DataItemError> {
+        let message = self.signature_data()?;
+        Ok(match self.signature_type {
+            SignatureType::Arweave => verify_rsa_pss(&self.owner, &message, &self.signature),
+            SignatureType::Ed25519 | SignatureType::Solana => {
+                verify_ed25519(&self.owner, &message, &self.signature)
+            }
+            SignatureType::Ethereum => {
+                let mut prefixed =
+                    format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+                prefixed.extend_from_slice(&message);
+                verify_secp256k1(&self.owner, &keccak256(&prefixed), &self.signature)
+            }
+        })
+    }
+
+    /// The owner's address: the SHA-256 of its public key, in base64url.
+    pub fn owner_address(&self) -> String {
+        encode_url(&sha256(&self.owner))
+    }
+
+    /// The item's ID: the SHA-256 of its signature, in base64url. Only meaningful once
+    /// the item is signed, since an unsigned item's signature is all zeros.
+    pub fn id(&self) -> String {
+        encode_url(&sha256(&self.signature))
+    }
+}
+
+/// A value to deep hash: a byte string, or a list of further values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeepHashChunk {
+    Blob(Vec<u8>),
+    List(Vec<DeepHashChunk>),
+}
+
+/// Arweave's deep hash: SHA-384 over each value tagged with its kind and length, folded
+/// over the items of a list.
+pub fn deep_hash(chunk: &DeepHashChunk) -> [u8; 48] {
+    match chunk {
+        DeepHashChunk::Blob(data) => {
+            let mut tagged = sha384(format!("blob{}", data.len()).as_bytes()).to_vec();
+            tagged.extend_from_slice(&sha384(data));
+            sha384(&tagged)
+        }
+        DeepHashChunk::List(chunks) => {
+            let mut acc = sha384(format!("list{}", chunks.len()).as_bytes());
+            for chunk in chunks {
+                let mut pair = acc.to_vec();
+                pair.extend_from_slice(&deep_hash(chunk));
+                acc = sha384(&pair);
+            }
+            acc
+        }
+    }
+}
+
+/// Encodes tags as an Avro array of `{ name: bytes, value: bytes }` records, or as
+/// nothing at all when there are none.
+pub fn encode_tags(tags: &[Tag]) -> Result<Vec<u8>, DataItemError> {
+    if tags.len() > MAX_TAGS {
+        return Err(DataItemError::TooManyTags(tags.len()));
+    }
+    let mut out = Vec::new();
+    if tags.is_empty() {
+        return Ok(out);
+    }
+    write_long(&mut out, tags.len() as i64);
+    for tag in tags {
+        if tag.name.len() > MAX_TAG_NAME_BYTES {
+            return Err(DataItemError::TagTooLong("name", tag.name.len()));
+        }
+        if tag.value.len() > MAX_TAG_VALUE_BYTES {
+            return Err(DataItemError::TagTooLong("value", tag.value.len()));
+        }
+        write_long(&mut out, tag.name.len() as i64);
+        out.extend_from_slice(tag.name.as_bytes());
+        write_long(&mut out, tag.value.len() as i64);
+        out.extend_from_slice(tag.value.as_bytes());
+    }
+    write_long(&mut out, 0);
+    Ok(out)
+}
+
+/// Decodes Avro-encoded tags, accepting blocks with a negative count and byte size.
+pub fn decode_tags(bytes: &[u8]) -> Result<Vec<Tag>, DataItemError> {
+    let mut tags = Vec::new();
+    if bytes.is_empty() {
+        return Ok(tags);
+    }
+    let mut reader = Reader { bytes, offset: 0 };
+    loop {
+        let mut count = reader.long()?;
+        if count == 0 {
+            break;
+        }
+        if count < 0 {
+            count = count
+                .checked_neg()
+                .ok_or(DataItemError::InvalidTags("bad block count"))?;
+            reader.long()?;
+        }
+        for _ in 0..count {
+            if tags.len() == MAX_TAGS {
+                return Err(DataItemError::TooManyTags(MAX_TAGS + 1));
+            }
+            let name = reader.string(MAX_TAG_NAME_BYTES, "name")?;
+            let value = reader.string(MAX_TAG_VALUE_BYTES, "value")?;
+            tags.push(Tag { name, value });
+        }
+    }
+    if reader.offset != bytes.len() {
+        return Err(DataItemError::InvalidTags("bytes after the tag array"));
+    }
+    Ok(tags)
+}
+
+fn check_length(field: &'static str, expected: usize, actual: usize) -> Result<(), DataItemError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(DataItemError::FieldLength(field, expected, actual))
+    }
+}
+
+// Avro longs are zigzag-encoded, then written 7 bits at a time, low bits first
+fn write_long(out: &mut Vec<u8>, n: i64) {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    while z >= 0x80 {
+        out.push((z as u8 & 0x7f) | 0x80);
+        z >>= 7;
+    }
+    out.push(z as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, field: &'static str) -> Result<&'a [u8], DataItemError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(DataItemError::Truncated(field))?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self, field: &'static str) -> Result<[u8; N], DataItemError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N, field)?);
+        Ok(array)
+    }
+
+    // A presence byte, then 32 bytes when it is 1
+    fn optional(&mut self, field: &'static str) -> Result<Option<[u8; 32]>, DataItemError> {
+        match self.take(1, field)?[0] {
+            0 => Ok(None),
+            1 => Ok(Some(self.array(field)?)),
+            byte => Err(DataItemError::InvalidPresence(field, byte)),
+        }
+    }
+
+    fn long(&mut self) -> Result<i64, DataItemError> {
+        let mut z: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1, "tags")?[0];
+            z |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((z >> 1) as i64 ^ -((z & 1) as i64));
+            }
+        }
+        Err(DataItemError::InvalidTags("long runs past 10 bytes"))
+    }
+
+    fn string(&mut self, max: usize, what: &'static str) -> Result<String, DataItemError> {
+        let len = usize::try_from(self.long()?)
+            .map_err(|_| DataItemError::InvalidTags("negative length"))?;
+        if len > max {
+            return Err(DataItemError::TagTooLong(what, len));
+        }
+        let bytes = self.take(len, "tags")?;
+        core::str::from_utf8(bytes)
+            .map(|s| s.to_string())
+            .map_err(|_| DataItemError::InvalidTags("tag is not UTF-8"))
+    }
+}
+
+fn encode(lua: &Lua, (msg, options): (LuaTable, Option<LuaTable>)) -> LuaResult<LuaString> {
+    let item = item_from_message(lua, &msg, parse_options(options)?)?;
+    lua.create_string(item.encode()?)
+}
+
+fn signature_data(lua: &Lua, (msg, options): (LuaTable, Option<LuaTable>)) -> LuaResult<LuaString> {
+    let item = item_from_message(lua, &msg, parse_options(options)?)?;
+    lua.create_string(item.signature_data()?)
+}
+
+fn verify(_lua: &Lua, bytes: LuaString) -> LuaResult<bool> {
+    Ok(DataItem::decode(&bytes.as_bytes())?.verify()?)
+}
+
+/// The address of whoever signed a message, when it carries its `Signature` and the
+/// signer's `Public-Key` in base64url and the signature verifies over the message's
+/// `Target`, `Anchor`, `Tags` and `Data`. The key's length picks the scheme.
+///
+/// Messages don't say which scheme signed them, and a 32-byte key may be Ed25519 or
+/// Solana, whose deep hashes differ in the signature type they commit to, so both
+/// are tried.
+pub(crate) fn verified_signer(lua: &Lua, msg: &LuaTable) -> LuaResult<Option<String>> {
+    let field = |key: &str| -> LuaResult<Option<Vec<u8>>> {
+        Ok(msg
+            .get::<Option<String>>(key)?
+            .and_then(|value| decode_url(value.as_bytes()).ok()))
+    };
+    let (Some(signature), Some(owner)) = (field("Signature")?, field("Public-Key")?) else {
+        return Ok(None);
+    };
+    let signature_types: &[SignatureType] = match owner.len() {
+        512 => &[SignatureType::Arweave],
+        32 => &[SignatureType::Ed25519, SignatureType::Solana],
+        65 => &[SignatureType::Ethereum],
+        _ => return Ok(None),
+    };
+    // Schemes sharing a key length share a signature length too
+    if signature.len() != signature_types[0].signature_len() {
+        return Ok(None);
+    }
+    for &signature_type in signature_types {
+        let options = Options {
+            signature_type,
+            owner: Some(owner.clone()),
+            signature: Some(signature.clone()),
+        };
+        // A message that cannot form a data item cannot carry a valid signature
+        let Ok(item) = item_from_message(lua, msg, options) else {
+            return Ok(None);
+        };
+        if item.verify() == Ok(true) {
+            return Ok(Some(item.owner_address()));
+        }
+    }
+    Ok(None)
+}
+
+fn id(_lua: &Lua, bytes: LuaString) -> LuaResult<String> {
+    Ok(DataItem::decode(&bytes.as_bytes())?.id())
+}
+
+fn deep_hash_value(lua: &Lua, value: LuaValue) -> LuaResult<LuaString> {
+    lua.create_string(deep_hash(&chunk_from_value(value)?))
+}
+
+// Strings become blobs and sequences become lists
+fn chunk_from_value(value: LuaValue) -> LuaResult<DeepHashChunk> {
+    match value {
+        LuaValue::String(s) => Ok(DeepHashChunk::Blob(s.as_bytes().to_vec())),
+        LuaValue::Table(list) => Ok(DeepHashChunk::List(
+            list.sequence_values::<LuaValue>()
+                .map(|value| chunk_from_value(value?))
+                .collect::<LuaResult<_>>()?,
+        )),
+        other => Err(LuaError::RuntimeError(format!(
+            "deep hash takes strings and lists of them, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// Options accepted from Lua.
+struct Options {
+    signature_type: SignatureType,
+    owner: Option<Vec<u8>>,
+    signature: Option<Vec<u8>>,
+}
+
+fn parse_options(options: Option<LuaTable>) -> LuaResult<Options> {
+    let Some(options) = options else {
+        return Ok(Options {
+            signature_type: SignatureType::Arweave,
+            owner: None,
+            signature: None,
+        });
+    };
+    let code = options.get::<Option<u16>>("signatureType")?.unwrap_or(1);
+    let bytes = |key: &str| -> LuaResult<Option<Vec<u8>>> {
+        Ok(options
+            .get::<Option<LuaString>>(key)?
+            .map(|value| value.as_bytes().to_vec()))
+    };
+    Ok(Options {
+        signature_type: SignatureType::from_code(code)
+            .ok_or(DataItemError::UnknownSignatureType(code))?,
+        owner: bytes("owner")?,
+        signature: bytes("signature")?,
+    })
+}
+
+// Reads an outbox message into an item, unsigned unless the options give a signature
+fn item_from_message(lua: &Lua, msg: &LuaTable, options: Options) -> LuaResult<DataItem> {
+    let mut item = DataItem::new(options.signature_type);
+    if let Some(owner) = options.owner {
+        item.owner = owner;
+    }
+    if let Some(signature) = options.signature {
+        item.signature = signature;
+    }
+
+    let message = Message::from_table(lua, msg)?;
+    if let Some(target) = message.target {
+        let id = decode_url(target.as_bytes())
+            .ok()
+            .and_then(|id| <[u8; 32]>::try_from(id).ok())
+            .ok_or_else(|| {
+                LuaError::RuntimeError(format!(
+                    "Target must be a base64url ID of 32 bytes, got \"{}\"",
+                    target
+                ))
+            })?;
+        item.target = Some(id);
+    }
+    if let Some(anchor) = msg.get::<Option<LuaString>>("Anchor")? {
+        let len = anchor.as_bytes().len();
+        let anchor = <[u8; 32]>::try_from(&anchor.as_bytes()[..])
+            .map_err(|_| DataItemError::FieldLength("anchor", 32, len))?;
+        item.anchor = Some(anchor);
+    }
+    item.tags = message.tags;
+    item.data = match message.data {
+        LuaValue::Nil => Vec::new(),
+        data @ (LuaValue::String(_) | LuaValue::Integer(_) | LuaValue::Number(_)) => lua
+            .coerce_string(data)?
+            .map(|data| data.as_bytes().to_vec())
+            .unwrap_or_default(),
+        other => {
+            return Err(LuaError::RuntimeError(format!(
+                "Data must be a string, got {}",
+                other.type_name()
+            )))
+        }
+    };
+    Ok(item)
+}
+
+fn decode(lua: &Lua, bytes: LuaString) -> LuaResult<LuaTable> {
+    let item = DataItem::decode(&bytes.as_bytes())?;
+    let table = lua.create_table()?;
+    table.set("SignatureType", item.signature_type.code())?;
+    table.set("Signature", lua.create_string(&item.signature)?)?;
+    table.set("Owner", lua.create_string(&item.owner)?)?;
+    table.set("Target", item.target.map(|id| encode_url(&id)))?;
+    table.set(
+        "Anchor",
+        item.anchor
+            .map(|anchor| lua.create_string(anchor))
+            .transpose()?,
+    )?;
+    table.set("Tags", lua.create_sequence_from(item.tags)?)?;
+    table.set("Data", lua.create_string(&item.data)?)?;
+    Ok(table)
+}
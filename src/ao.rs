@@ -1,8 +1,29 @@
 use super::*;
+use crate::ans104::{verified_signer, MAX_TAGS, MAX_TAG_NAME_BYTES, MAX_TAG_VALUE_BYTES};
+use crate::message::{Message, Process};
 use crate::utils::is_array;
+use alloc::vec;
+
+// `receive` waits in `Handlers.receive`, which yields the running coroutine; a Rust
+// callback can't yield across the C boundary, so these stay small Lua closures.
+const MESSAGE_RECEIVE_SRC: &str = r##"
+local message, reference = ...
+return function(...)
+  local from = message.Target
+  if select("#", ...) == 1 then from = select(1, ...) end
+  return Handlers.receive({ From = from, ["X-Reference"] = reference })
+end
+"##;
+
+const SPAWN_RECEIVE_SRC: &str = r#"
+local reference = ...
+return function()
+  return Handlers.receive({ Action = "Spawned", From = ao.id, Reference = reference })
+end
+"#;
 
 /// Registers the `ao` module with Lua, initializing the `ao` table with fields and functions.
-#[mlua::lua_module]
+#[cfg_attr(feature = "module", mlua::lua_module)]
 pub fn ao(lua: &Lua) -> LuaResult<LuaTable> {
     // load the ao module
     // let require: LuaFunction = lua.globals().get("require")?;
@@ -39,6 +60,27 @@ pub fn ao(lua: &Lua) -> LuaResult<LuaTable> {
                 .unwrap_or_else(|_| lua.create_table().unwrap()),
         )?;
         ao_lua.set("reference", old_ao.get("reference").unwrap_or(0))?;
+        ao_lua.set(
+            "validation",
+            old_ao
+                .get::<Option<String>>("validation")?
+                .unwrap_or_else(|| "warn".to_string()),
+        )?;
+        ao_lua.set(
+            "trust",
+            old_ao
+                .get::<Option<String>>("trust")?
+                .unwrap_or_else(|| "authority".to_string()),
+        )?;
+        for config in ["limits", "sandbox"] {
+            ao_lua.set(
+                config,
+                match old_ao.get::<Option<LuaTable>>(config)? {
+                    Some(table) => table,
+                    None => lua.create_table()?,
+                },
+            )?;
+        }
         ao_lua.set(
             "outbox",
             old_ao.get("outbox").unwrap_or_else(|_| {
@@ -57,6 +99,10 @@ pub fn ao(lua: &Lua) -> LuaResult<LuaTable> {
         ao_lua.set("_module", "")?;
         ao_lua.set("authorities", lua.create_table()?)?;
         ao_lua.set("reference", 0)?;
+        ao_lua.set("validation", "warn")?;
+        ao_lua.set("trust", "authority")?;
+        ao_lua.set("limits", lua.create_table()?)?;
+        ao_lua.set("sandbox", lua.create_table()?)?;
         let outbox = lua.create_table()?;
         outbox.set("Output", lua.create_table()?)?;
         outbox.set("Messages", lua.create_table()?)?;
@@ -125,6 +171,7 @@ pub fn ao(lua: &Lua) -> LuaResult<LuaTable> {
     // Set implemented Rust functions
     ao_lua.set("clone", lua.create_function(clone)?)?;
     ao_lua.set("normalize", lua.create_function(normalize)?)?;
+    ao_lua.set("enrich", lua.create_function(enrich)?)?;
     ao_lua.set("sanitize", lua.create_function(sanitize)?)?;
     ao_lua.set("init", lua.create_function(init)?)?;
     ao_lua.set("log", lua.create_function(log)?)?;
@@ -137,50 +184,31 @@ pub fn ao(lua: &Lua) -> LuaResult<LuaTable> {
     Ok(ao_lua)
 }
 
-fn init(lua: &Lua, env: LuaTable) -> LuaResult<()> {
+pub(crate) fn init(lua: &Lua, env: LuaTable) -> LuaResult<()> {
     let ao: LuaTable = lua.globals().get("ao")?;
+    let process: Process = env.get("Process")?;
 
     // Set ao.id if empty
     let current_id: String = ao.get("id").unwrap_or_default();
     if current_id.is_empty() {
-        let process: LuaTable = env.get("Process")?;
-        let new_id: String = process.get("Id")?;
-        ao.set("id", new_id)?;
+        ao.set("id", process.id.as_str())?;
     }
 
     // Set ao._module from Process.Tags
     let current_module: String = ao.get("_module").unwrap_or_default();
     if current_module.is_empty() {
-        let process: LuaTable = env.get("Process")?;
-        let tags: LuaTable = process.get("Tags")?;
-
-        for pair in tags.pairs::<i32, LuaTable>() {
-            let (_, tag) = pair?;
-            let name: String = tag.get("name")?;
-            if name == "Module" {
-                let value: String = tag.get("value")?;
-                ao.set("_module", value)?;
-                break;
-            }
+        if let Some(module) = process.tag("Module") {
+            ao.set("_module", module)?;
         }
     }
 
     // Populate authorities if empty
     let authorities: LuaTable = ao.get("authorities")?;
     if authorities.len()? == 0 {
-        let process: LuaTable = env.get("Process")?;
-        let tags: LuaTable = process.get("Tags")?;
-        let new_authorities = lua.create_table()?;
-
-        for pair in tags.pairs::<i32, LuaTable>() {
-            let (_, tag) = pair?;
-            let name: String = tag.get("name")?;
-            if name == "Authority" {
-                let value: String = tag.get("value")?;
-                new_authorities.push(value)?;
-            }
-        }
-        ao.set("authorities", new_authorities)?;
+        ao.set(
+            "authorities",
+            lua.create_sequence_from(process.tag_values("Authority"))?,
+        )?;
     }
 
     // Initialize outbox with pure Lua tables
@@ -237,7 +265,7 @@ fn clear_outbox(lua: &Lua, _: ()) -> LuaResult<()> {
     Ok(())
 }
 
-fn send(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
+pub(crate) fn send(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
     let ao: LuaTable = lua.globals().get("ao")?;
 
     // Assert msg is a table
@@ -246,15 +274,14 @@ fn send(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
     let msg_type: String = type_fn.call::<String>(msg.clone())?;
     lua_assert.call::<()>((msg_type == "table", "msg should be a table"))?;
 
-    // Increment reference
-    let mut reference: i64 = ao.get("reference")?;
-    reference += 1;
-    ao.set("reference", reference)?;
+    // Next reference, stored once the message passes validation
+    let reference: i64 = ao.get::<i64>("reference")? + 1;
     let reference_str = reference.to_string();
 
     // Create base message
+    let target = msg.get::<String>("Target")?;
     let message = lua.create_table()?;
-    message.set("Target", msg.get::<String>("Target")?)?;
+    message.set("Target", target.as_str())?;
     message.set("Data", msg.get::<LuaValue>("Data")?)?;
     message.set("Anchor", format!("{:032}", reference))?;
 
@@ -272,34 +299,14 @@ fn send(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
     tags.set(tag_index, create_tag(lua, "Reference", &reference_str)?)?;
     tag_index += 1;
 
-    // Add custom tags from msg root
-    for pair_result in msg.pairs::<LuaValue, LuaValue>() {
-        let (key_value, val_value) = pair_result?;
-        let key = key_value.to_string()?;
-        if !["Target", "Data", "Anchor", "Tags", "From"].contains(&&*key) {
-            let value = val_value.to_string()?;
-            tags.set(tag_index, create_tag(lua, &key, &value)?)?;
-            tag_index += 1;
-        }
-    }
-
-    // Handle msg.Tags
-    if let Ok(msg_tags) = msg.get::<LuaTable>("Tags") {
-        if is_array(lua, LuaValue::Table(msg_tags.clone()))? {
-            for pair in msg_tags.sequence_values::<LuaTable>() {
-                let o = pair?;
-                tags.set(tag_index, o)?;
-                tag_index += 1;
-            }
-        } else {
-            for pair_result in msg_tags.pairs::<LuaValue, LuaValue>() {
-                let (k, v) = pair_result?;
-                let tag = create_tag(lua, &k.to_string()?, &v.to_string()?)?;
-                tags.set(tag_index, tag)?;
-                tag_index += 1;
-            }
-        }
+    // Add custom tags from msg root and msg.Tags
+    let defaults = tag_index - 1;
+    for tag in custom_tags(lua, &msg)? {
+        tags.set(tag_index, tag)?;
+        tag_index += 1;
     }
+    validate(lua, &ao, "ao.send", Some(&target), &tags, defaults)?;
+    ao.set("reference", reference)?;
     message.set("Tags", tags)?;
 
     // Early return if Handlers is not present
@@ -344,25 +351,12 @@ fn send(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
         })?,
     )?;
 
-    // Add receive function
-    let message_clone = message.clone();
-    let reference_str_clone = reference_str.clone();
-    message.set(
-        "receive",
-        lua.create_function(
-            move |lua, args: LuaMultiValue| -> LuaResult<LuaMultiValue> {
-                let handlers: LuaTable = lua.globals().get("Handlers")?;
-                let from = match args.len() {
-                    1 => args[0].clone(),
-                    _ => message_clone.get::<String>("Target")?.into_lua(lua)?,
-                };
-                let params = lua.create_table()?;
-                params.set("From", from)?;
-                params.set("X-Reference", &*reference_str_clone)?;
-                handlers.get::<LuaFunction>("receive")?.call(params)
-            },
-        )?,
-    )?;
+    // Add receive function, in Lua so it can yield the calling coroutine
+    let receive: LuaFunction = lua
+        .load(MESSAGE_RECEIVE_SRC)
+        .set_name("=ao.send.receive")
+        .call((message.clone(), &*reference_str))?;
+    message.set("receive", receive)?;
 
     Ok(message)
 }
@@ -375,6 +369,153 @@ fn create_tag(lua: &Lua, name: &str, value: &str) -> LuaResult<LuaTable> {
     Ok(tag)
 }
 
+/// Builds the caller's tags in a stable order, since tag order is part of the signed
+/// data item: fields in the msg root sorted by name, then `msg.Tags` as given when it
+/// is a list, or sorted by name when it is a map.
+fn custom_tags(lua: &Lua, msg: &LuaTable) -> LuaResult<Vec<LuaTable>> {
+    let mut root = Vec::new();
+    for pair in msg.pairs::<LuaValue, LuaValue>() {
+        let (key, value) = pair?;
+        let key = key.to_string()?;
+        if !["Target", "Data", "Anchor", "Tags", "From"].contains(&key.as_str()) {
+            root.push((key, value.to_string()?));
+        }
+    }
+    let mut tags = sorted_tags(lua, root)?;
+
+    if let Ok(msg_tags) = msg.get::<LuaTable>("Tags") {
+        if is_array(lua, LuaValue::Table(msg_tags.clone()))? {
+            for tag in msg_tags.sequence_values::<LuaTable>() {
+                tags.push(tag?);
+            }
+        } else {
+            let mut named = Vec::new();
+            for pair in msg_tags.pairs::<LuaValue, LuaValue>() {
+                let (name, value) = pair?;
+                named.push((name.to_string()?, value.to_string()?));
+            }
+            tags.extend(sorted_tags(lua, named)?);
+        }
+    }
+    Ok(tags)
+}
+
+// Sorts by name, then value, so keys that stringify alike (1 and "1") still order stably
+fn sorted_tags(lua: &Lua, mut pairs: Vec<(String, String)>) -> LuaResult<Vec<LuaTable>> {
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| create_tag(lua, name, value))
+        .collect()
+}
+
+/// Checks an outgoing message against what the network accepts: a 43-character
+/// base64url `Target`, at most `MAX_TAGS` tags within the byte limits, and no custom
+/// tag reusing a name among the first `defaults` tags, which ao sets itself.
+///
+/// `ao.validation` picks what a problem does: `"strict"` raises it, `"warn"` (the
+/// default) appends it to `ao.outbox.Warnings` and sends anyway, `"off"` skips the checks.
+fn validate(
+    lua: &Lua,
+    ao: &LuaTable,
+    call: &str,
+    target: Option<&str>,
+    tags: &LuaTable,
+    defaults: usize,
+) -> LuaResult<()> {
+    let mode = ao.get::<Option<String>>("validation")?;
+    let strict = match mode.as_deref() {
+        Some("strict") => true,
+        None | Some("warn") => false,
+        Some("off") => return Ok(()),
+        Some(other) => {
+            return Err(LuaError::RuntimeError(format!(
+                "ao.validation must be \"strict\", \"warn\" or \"off\", got \"{}\"",
+                other
+            )))
+        }
+    };
+
+    let mut problems = Vec::new();
+    if let Some(target) = target {
+        let is_id = target.len() == 43
+            && crate::base64::decode_url(target.as_bytes()).is_ok_and(|id| id.len() == 32);
+        if !is_id {
+            problems.push(format!(
+                "Target must be a 43-character base64url ID, got \"{}\"",
+                target
+            ));
+        }
+    }
+
+    let count = tags.raw_len();
+    if count > MAX_TAGS {
+        problems.push(format!("{} tags, at most {} allowed", count, MAX_TAGS));
+    }
+    let mut reserved = Vec::with_capacity(defaults);
+    for (i, tag) in tags.sequence_values::<LuaTable>().enumerate() {
+        let tag = tag?;
+        let name = tag.get::<Option<LuaString>>("name")?;
+        let value = tag.get::<Option<LuaString>>("value")?;
+        let Some(name) = name else {
+            problems.push(format!("tag {} has no name", i + 1));
+            continue;
+        };
+        let name = name.to_str()?.to_string();
+        if i < defaults {
+            reserved.push(name);
+            continue;
+        }
+        if name.is_empty() {
+            problems.push(format!("tag {} has an empty name", i + 1));
+        } else if name.len() > MAX_TAG_NAME_BYTES {
+            problems.push(format!(
+                "tag name {}... is {} bytes, at most {} allowed",
+                name.chars().take(32).collect::<String>(),
+                name.len(),
+                MAX_TAG_NAME_BYTES
+            ));
+        }
+        if reserved.contains(&name) {
+            problems.push(format!("tag {} is reserved, ao sets it", name));
+        }
+        match value {
+            None => problems.push(format!("tag {} has no value", name)),
+            Some(value) if value.as_bytes().len() > MAX_TAG_VALUE_BYTES => problems.push(format!(
+                "tag {} value is {} bytes, at most {} allowed",
+                name,
+                value.as_bytes().len(),
+                MAX_TAG_VALUE_BYTES
+            )),
+            Some(_) => {}
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    if strict {
+        return Err(LuaError::RuntimeError(format!(
+            "{}: {}",
+            call,
+            problems.join("; ")
+        )));
+    }
+    let outbox: LuaTable = ao.get("outbox")?;
+    let warnings = match outbox.get::<Option<LuaTable>>("Warnings")? {
+        Some(warnings) => warnings,
+        None => {
+            let warnings = lua.create_table()?;
+            outbox.set("Warnings", warnings.clone())?;
+            warnings
+        }
+    };
+    for problem in problems {
+        warnings.push(format!("{}: {}", call, problem))?;
+    }
+    Ok(())
+}
+
 fn includes(lua: &Lua, list: LuaTable) -> LuaResult<LuaFunction> {
     let func = move |_lua: &Lua, key: LuaValue| -> LuaResult<bool> {
         for pair in list.pairs::<i32, LuaValue>() {
@@ -401,8 +542,8 @@ fn clone(lua: &Lua, (obj, seen): (LuaValue, Option<LuaTable>)) -> LuaResult<LuaV
 
     // Handle seen tables
     let seen = seen.unwrap_or_else(|| lua.create_table().unwrap());
-    if let Ok(existing) = seen.get::<LuaValue>(LuaValue::Table(obj_table.clone())) {
-        return Ok(existing);
+    if let Some(existing) = seen.get::<Option<LuaTable>>(obj_table.clone())? {
+        return Ok(LuaValue::Table(existing));
     }
 
     // Create new table and mark as seen
@@ -425,27 +566,83 @@ fn clone(lua: &Lua, (obj, seen): (LuaValue, Option<LuaTable>)) -> LuaResult<LuaV
     Ok(LuaValue::Table(res))
 }
 
-fn normalize(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
-    let tags: LuaTable = msg.get("Tags")?;
+pub(crate) fn normalize(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
+    let message = Message::from_table(lua, &msg)?;
     let non_extractable_tags: LuaTable = lua
         .globals()
         .get::<LuaTable>("ao")?
         .get("nonExtractableTags")?;
     let includes_fn = includes(lua, non_extractable_tags)?;
 
-    for pair in tags.sequence_values::<LuaTable>() {
-        let tag = pair?;
-        let name: String = tag.get("name")?;
-        let includes_result: bool = includes_fn.call(name.clone())?;
+    for tag in message.tags {
+        let includes_result: bool = includes_fn.call(tag.name.as_str())?;
         if !includes_result {
-            let value: LuaValue = tag.get("value")?;
-            msg.set(name, value)?;
+            msg.set(tag.name, tag.value)?;
         }
     }
 
     Ok(msg)
 }
 
+/// Attaches `msg.reply` and `msg.forward` to a normalized message. Both take the
+/// message as a bound argument rather than a captured one, so Lua can still collect it.
+pub(crate) fn enrich(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
+    msg.set("reply", lua.create_function(reply)?.bind(msg.clone())?)?;
+    msg.set("forward", lua.create_function(forward)?.bind(msg.clone())?)?;
+    Ok(msg)
+}
+
+// Sends `reply` to the message's `Reply-To`, or else its own `Target` or the sender
+fn reply(lua: &Lua, (msg, reply): (LuaTable, LuaTable)) -> LuaResult<LuaTable> {
+    let target = match msg.get::<Option<String>>("Reply-To")? {
+        Some(reply_to) => Some(reply_to),
+        None => reply
+            .get::<Option<String>>("Target")?
+            .or(msg.get::<Option<String>>("From")?),
+    };
+    reply.set("Target", target)?;
+    reply.set("X-Reference", x_reference(&msg)?)?;
+    reply.set(
+        "X-Origin",
+        msg.get::<Option<String>>("X-Origin")?.unwrap_or_default(),
+    )?;
+    send(lua, reply)
+}
+
+// Sends a sanitized copy of the message, with `extra` fields merged in, to `target`
+fn forward(
+    lua: &Lua,
+    (msg, target, extra): (LuaTable, String, Option<LuaTable>),
+) -> LuaResult<LuaTable> {
+    let forwarded = sanitize(lua, msg.clone())?;
+    if let Some(extra) = extra {
+        for pair in extra.pairs::<LuaValue, LuaValue>() {
+            let (key, value) = pair?;
+            forwarded.set(key, value)?;
+        }
+    }
+    let origin = match msg.get::<Option<String>>("X-Origin")? {
+        Some(origin) => origin,
+        None => msg.get::<Option<String>>("From")?.unwrap_or_default(),
+    };
+    let ao: LuaTable = lua.globals().get("ao")?;
+    forwarded.set("Target", target)?;
+    forwarded.set("X-Reference", x_reference(&msg)?)?;
+    forwarded.set("X-Origin", origin)?;
+    forwarded.set("Forwarded-By", ao.get::<String>("id")?)?;
+    forwarded.set("reply", LuaValue::Nil)?;
+    forwarded.set("forward", LuaValue::Nil)?;
+    send(lua, forwarded)
+}
+
+// The reference a reply or forward answers: the one the message answered, or its own
+fn x_reference(msg: &LuaTable) -> LuaResult<String> {
+    match msg.get::<Option<String>>("X-Reference")? {
+        Some(reference) => Ok(reference),
+        None => Ok(msg.get::<Option<String>>("Reference")?.unwrap_or_default()),
+    }
+}
+
 fn sanitize(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
     let cloned_value = clone(lua, (LuaValue::Table(msg), None))?;
     let new_msg = match cloned_value {
@@ -476,18 +673,16 @@ fn sanitize(lua: &Lua, msg: LuaTable) -> LuaResult<LuaTable> {
 fn spawn(lua: &Lua, (module, msg): (String, LuaTable)) -> LuaResult<LuaTable> {
     let ao: LuaTable = lua.globals().get("ao")?;
 
-    // Get and increment reference
-    let mut reference: i64 = ao.get("reference")?;
-    reference += 1;
-    ao.set("reference", reference)?;
+    // Next reference, stored once the spawn passes validation
+    let reference: i64 = ao.get::<i64>("reference")? + 1;
     let reference_str = reference.to_string();
 
     // Create spawn table
     let spawn = lua.create_table()?;
-    let default_str = lua.create_string("NODATA")?;
-    let data = msg
-        .get("Data")
-        .unwrap_or_else(|_| LuaValue::String(default_str.clone()));
+    let data = match msg.get::<LuaValue>("Data")? {
+        LuaValue::Nil | LuaValue::Boolean(false) => LuaValue::String(lua.create_string("NODATA")?),
+        data => data,
+    };
     spawn.set("Data", data)?;
     spawn.set("Anchor", format!("{:032}", reference))?;
 
@@ -529,42 +724,14 @@ fn spawn(lua: &Lua, (module, msg): (String, LuaTable)) -> LuaResult<LuaTable> {
     tags.set(tag_index, create_tag(lua, "Reference", &reference_str)?)?;
     tag_index += 1;
 
-    // Add custom tags from msg root
-    let excluded = ["Target", "Data", "Anchor", "Tags", "From"];
-    for pair in msg.pairs::<String, LuaValue>() {
-        let (key, value) = pair?;
-        if !excluded.contains(&key.as_str()) {
-            let value_str = match value {
-                LuaValue::Nil => String::from(""),
-                LuaValue::String(s) => s.to_str()?.to_string(),
-                _ => value.to_string()?,
-            };
-            tags.set(tag_index, create_tag(lua, &key, &value_str)?)?;
-            tag_index += 1;
-        }
-    }
-
-    // Handle msg.Tags
-    if let Ok(msg_tags) = msg.get::<LuaTable>("Tags") {
-        if crate::utils::is_array(lua, LuaValue::Table(msg_tags.clone()))? {
-            for pair in msg_tags.sequence_values::<LuaTable>() {
-                let tag = pair?;
-                tags.set(tag_index, tag)?;
-                tag_index += 1;
-            }
-        } else {
-            for pair in msg_tags.pairs::<String, LuaValue>() {
-                let (k, v) = pair?;
-                let value_str = match v {
-                    LuaValue::Nil => String::from(""),
-                    LuaValue::String(s) => s.to_str()?.to_string(),
-                    _ => v.to_string()?,
-                };
-                tags.set(tag_index, create_tag(lua, &k, &value_str)?)?;
-                tag_index += 1;
-            }
-        }
+    // Add custom tags from msg root and msg.Tags
+    let defaults = tag_index - 1;
+    for tag in custom_tags(lua, &msg)? {
+        tags.set(tag_index, tag)?;
+        tag_index += 1;
     }
+    validate(lua, &ao, "ao.spawn", None, &tags, defaults)?;
+    ao.set("reference", reference)?;
     spawn.set("Tags", tags)?;
 
     // Check for Handlers
@@ -598,20 +765,12 @@ fn spawn(lua: &Lua, (module, msg): (String, LuaTable)) -> LuaResult<LuaTable> {
             })?,
         )?;
 
-        // Add receive function
-        let reference_str_clone = reference_str.clone();
-        spawn.set(
-            "receive",
-            lua.create_function(move |lua, _: ()| -> LuaResult<LuaMultiValue> {
-                let handlers: LuaTable = lua.globals().get("Handlers")?;
-                let ao: LuaTable = lua.globals().get("ao")?;
-                let pattern = lua.create_table()?;
-                pattern.set("Action", "Spawned")?;
-                pattern.set("From", ao.get::<String>("id").unwrap_or_default())?;
-                pattern.set("Reference", reference_str_clone.clone())?;
-                handlers.get::<LuaFunction>("receive")?.call(pattern)
-            })?,
-        )?;
+        // Add receive function, in Lua so it can yield the calling coroutine
+        let receive: LuaFunction = lua
+            .load(SPAWN_RECEIVE_SRC)
+            .set_name("=ao.spawn.receive")
+            .call(&*reference_str)?;
+        spawn.set("receive", receive)?;
     }
 
     Ok(spawn)
@@ -648,16 +807,41 @@ fn assign(lua: &Lua, assignment: LuaTable) -> LuaResult<()> {
     Ok(())
 }
 
-fn is_trusted(lua: &Lua, msg: LuaTable) -> LuaResult<bool> {
+/// Whether a message comes from one of `ao.authorities`.
+///
+/// `ao.trust` picks what counts: `"authority"` (the default) takes the message's `From`
+/// or `Owner` at its word, while `"signature"` requires its `Signature` to verify under
+/// its `Public-Key`, whose address must be the `Owner`, if any, and an authority.
+pub(crate) fn is_trusted(lua: &Lua, msg: LuaTable) -> LuaResult<bool> {
     let ao: LuaTable = lua.globals().get("ao")?;
     let authorities: LuaTable = ao.get("authorities")?;
-
-    let from: Option<String> = msg.get("From")?;
-    let owner: Option<String> = msg.get("Owner")?;
+    let message = Message::from_table(lua, &msg)?;
+
+    let mode = ao.get::<Option<String>>("trust")?;
+    let signers = match mode.as_deref() {
+        None | Some("authority") => vec![message.from, message.owner],
+        Some("signature") => match verified_signer(lua, &msg)? {
+            Some(signer)
+                if message
+                    .owner
+                    .as_ref()
+                    .map_or(true, |owner| *owner == signer) =>
+            {
+                vec![Some(signer)]
+            }
+            _ => return Ok(false),
+        },
+        Some(other) => {
+            return Err(LuaError::RuntimeError(format!(
+                "ao.trust must be \"authority\" or \"signature\", got \"{}\"",
+                other
+            )))
+        }
+    };
 
     for i in 1..=authorities.len()? {
         let authority: String = authorities.get(i)?;
-        if from.as_ref() == Some(&authority) || owner.as_ref() == Some(&authority) {
+        if signers.contains(&Some(authority)) {
             return Ok(true);
         }
     }
@@ -665,17 +849,19 @@ fn is_trusted(lua: &Lua, msg: LuaTable) -> LuaResult<bool> {
     Ok(false)
 }
 
-fn result(lua: &Lua, res: LuaTable) -> LuaResult<LuaTable> {
+pub(crate) fn result(lua: &Lua, res: LuaTable) -> LuaResult<LuaTable> {
     let ao: LuaTable = lua.globals().get("ao")?;
     let outbox: LuaTable = ao.get("outbox")?;
 
     // Check for errors first
-    let error_msg: Option<String> = res.get("Error")?;
-    let outbox_error: Option<String> = outbox.get("Error")?;
+    let error: LuaValue = match res.get("Error")? {
+        LuaValue::Nil => outbox.get("Error")?,
+        error => error,
+    };
 
-    if error_msg.is_some() || outbox_error.is_some() {
+    if !error.is_nil() {
         let error_table = lua.create_table()?;
-        error_table.set("Error", error_msg.or(outbox_error))?;
+        error_table.set("Error", error)?;
         return Ok(error_table);
     }
 
@@ -689,6 +875,7 @@ fn result(lua: &Lua, res: LuaTable) -> LuaResult<LuaTable> {
     result_table.set("Messages", outbox.get::<LuaTable>("Messages")?)?;
     result_table.set("Spawns", outbox.get::<LuaTable>("Spawns")?)?;
     result_table.set("Assignments", outbox.get::<LuaTable>("Assignments")?)?;
+    result_table.set("Warnings", outbox.get::<Option<LuaTable>>("Warnings")?)?;
 
     Ok(result_table)
 }
@@ -1,7 +1,8 @@
 use super::*;
+use crate::message::Message;
 use crate::utils::matches_spec;
 
-#[mlua::lua_module]
+#[cfg_attr(feature = "module", mlua::lua_module)]
 pub fn assignment(lua: &Lua) -> LuaResult<LuaTable> {
     let exports = lua.create_table()?;
     exports.set("_version", "0.1.0")?;
@@ -9,7 +10,7 @@ pub fn assignment(lua: &Lua) -> LuaResult<LuaTable> {
     Ok(exports)
 }
 
-fn init(lua: &Lua, ao: LuaTable) -> LuaResult<()> {
+pub(crate) fn init(lua: &Lua, ao: LuaTable) -> LuaResult<()> {
     if ao.get::<Option<LuaTable>>("assignables")?.is_none() {
         ao.set("assignables", lua.create_table()?)?;
     }
@@ -82,10 +83,9 @@ fn init(lua: &Lua, ao: LuaTable) -> LuaResult<()> {
     ao.set("removeAssignable", remove_assignable)?;
 
     let ao_clone = ao.clone();
-    let is_assignment = lua.create_function(move |_lua, msg: LuaTable| {
-        let target: String = msg.get("Target")?;
+    let is_assignment = lua.create_function(move |_lua, msg: Message| {
         let ao_id: String = ao_clone.get("id")?;
-        Ok(target != ao_id)
+        Ok(msg.target.as_deref() != Some(ao_id.as_str()))
     })?;
     ao.set("isAssignment", is_assignment)?;
 
@@ -112,7 +112,7 @@ fn init(lua: &Lua, ao: LuaTable) -> LuaResult<()> {
     Ok(())
 }
 
-fn find_index_by_prop(
+pub(crate) fn find_index_by_prop(
     _lua: &Lua,
     array: LuaTable,
     prop: &str,
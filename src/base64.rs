@@ -0,0 +1,408 @@
+use super::*;
+use core::fmt;
+
+const VERSION: &str = "0.1.0";
+
+const STANDARD_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Registers the `base64` module with Lua, exporting one-shot `encode`/`decode`
+/// and the streaming `encoder`/`decoder` constructors.
+///
+/// Every function takes an optional options table:
+/// - `alphabet`: `"standard"` (default) or `"url"`.
+/// - `padding`: whether `=` padding is written (and, when strict, required).
+///   Defaults to `true` for the standard alphabet and `false` for the URL-safe one.
+/// - `strict` (decoding only): reject whitespace, foreign characters, bad padding
+///   and non-canonical trailing bits instead of skipping over them. Defaults to `false`.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "base64"))]
+pub fn base64(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set("encode", lua.create_function(encode)?)?;
+    exports.set("decode", lua.create_function(decode)?)?;
+    exports.set("encoder", lua.create_function(encoder)?)?;
+    exports.set("decoder", lua.create_function(decoder)?)?;
+    Ok(exports)
+}
+
+/// The character set used to map 6-bit groups to bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alphabet {
+    Standard,
+    UrlSafe,
+}
+
+impl Alphabet {
+    fn chars(self) -> &'static [u8; 64] {
+        match self {
+            Alphabet::Standard => STANDARD_CHARS,
+            Alphabet::UrlSafe => URL_SAFE_CHARS,
+        }
+    }
+
+    fn default_padding(self) -> bool {
+        self == Alphabet::Standard
+    }
+
+    fn decode_char(self, c: u8) -> Option<u8> {
+        match c {
+            b'A'..=b'Z' => Some(c - b'A'),
+            b'a'..=b'z' => Some(c - b'a' + 26),
+            b'0'..=b'9' => Some(c - b'0' + 52),
+            b'+' if self == Alphabet::Standard => Some(62),
+            b'/' if self == Alphabet::Standard => Some(63),
+            b'-' if self == Alphabet::UrlSafe => Some(62),
+            b'_' if self == Alphabet::UrlSafe => Some(63),
+            _ => None,
+        }
+    }
+}
+
+/// Reasons a base64 input can be rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A byte outside the alphabet, with its offset in the input.
+    InvalidByte(usize, u8),
+    /// The input ends with a single dangling character.
+    InvalidLength,
+    /// Padding is missing, misplaced or present where it isn't allowed.
+    InvalidPadding,
+    /// The last character carries bits that don't belong to the decoded data.
+    TrailingBits,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidByte(offset, byte) => {
+                write!(f, "invalid base64 byte 0x{:02x} at offset {}", byte, offset)
+            }
+            DecodeError::InvalidLength => write!(f, "invalid base64 length"),
+            DecodeError::InvalidPadding => write!(f, "invalid base64 padding"),
+            DecodeError::TrailingBits => write!(f, "invalid base64 trailing bits"),
+        }
+    }
+}
+
+impl From<DecodeError> for LuaError {
+    fn from(err: DecodeError) -> Self {
+        LuaError::RuntimeError(err.to_string())
+    }
+}
+
+/// Incremental encoder; feed it chunks with `update` and flush it with `finish`.
+pub struct Encoder {
+    alphabet: Alphabet,
+    padding: bool,
+    pending: [u8; 3],
+    pending_len: usize,
+}
+
+impl Encoder {
+    pub fn new(alphabet: Alphabet, padding: bool) -> Self {
+        Encoder {
+            alphabet,
+            padding,
+            pending: [0; 3],
+            pending_len: 0,
+        }
+    }
+
+    /// Encodes every complete 3-byte group of `input` into `out`, buffering the remainder.
+    pub fn update(&mut self, mut input: &[u8], out: &mut String) {
+        while self.pending_len > 0 && self.pending_len < 3 && !input.is_empty() {
+            self.pending[self.pending_len] = input[0];
+            self.pending_len += 1;
+            input = &input[1..];
+        }
+        if self.pending_len == 3 {
+            let group = self.pending;
+            self.write_group(&group, out);
+            self.pending_len = 0;
+        }
+        let mut groups = input.chunks_exact(3);
+        for group in &mut groups {
+            self.write_group(group, out);
+        }
+        let rest = groups.remainder();
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+    }
+
+    /// Writes the buffered tail, with padding if enabled, and resets the encoder.
+    pub fn finish(&mut self, out: &mut String) {
+        if self.pending_len > 0 {
+            let group = self.pending;
+            self.write_group(&group[..self.pending_len], out);
+            self.pending_len = 0;
+        }
+    }
+
+    fn write_group(&self, group: &[u8], out: &mut String) {
+        let chars = self.alphabet.chars();
+        let b0 = group[0] as usize;
+        let b1 = group.get(1).copied().unwrap_or(0) as usize;
+        let b2 = group.get(2).copied().unwrap_or(0) as usize;
+        out.push(chars[b0 >> 2] as char);
+        out.push(chars[((b0 & 0x03) << 4) | (b1 >> 4)] as char);
+        if group.len() > 1 {
+            out.push(chars[((b1 & 0x0f) << 2) | (b2 >> 6)] as char);
+        } else if self.padding {
+            out.push('=');
+        }
+        if group.len() > 2 {
+            out.push(chars[b2 & 0x3f] as char);
+        } else if self.padding {
+            out.push('=');
+        }
+    }
+}
+
+/// Incremental decoder; feed it chunks with `update` and validate the tail with `finish`.
+pub struct Decoder {
+    alphabet: Alphabet,
+    padding: bool,
+    strict: bool,
+    quad: [u8; 4],
+    quad_len: usize,
+    padding_len: usize,
+    offset: usize,
+}
+
+impl Decoder {
+    pub fn new(alphabet: Alphabet, padding: bool, strict: bool) -> Self {
+        Decoder {
+            alphabet,
+            padding,
+            strict,
+            quad: [0; 4],
+            quad_len: 0,
+            padding_len: 0,
+            offset: 0,
+        }
+    }
+
+    /// Decodes every complete 4-character group of `input` into `out`, buffering the remainder.
+    pub fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        for &c in input {
+            let offset = self.offset;
+            self.offset += 1;
+
+            if c == b'=' {
+                if self.strict && (!self.padding || self.quad_len < 2 || self.padding_len >= 2) {
+                    return Err(DecodeError::InvalidPadding);
+                }
+                self.padding_len += 1;
+                continue;
+            }
+            if self.padding_len > 0 {
+                // Lenient decoding stops at the first padding character
+                if self.strict {
+                    return Err(DecodeError::InvalidPadding);
+                }
+                continue;
+            }
+
+            let value = match self.alphabet.decode_char(c) {
+                Some(value) => value,
+                None if self.strict => return Err(DecodeError::InvalidByte(offset, c)),
+                None => match c {
+                    // Accept the other alphabet's two symbols when lenient
+                    b'+' | b'-' => 62,
+                    b'/' | b'_' => 63,
+                    _ => continue,
+                },
+            };
+            self.quad[self.quad_len] = value;
+            self.quad_len += 1;
+            if self.quad_len == 4 {
+                let q = self.quad;
+                out.push((q[0] << 2) | (q[1] >> 4));
+                out.push((q[1] << 4) | (q[2] >> 2));
+                out.push((q[2] << 6) | q[3]);
+                self.quad_len = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the buffered tail, checks padding and trailing bits, and resets the decoder.
+    pub fn finish(&mut self, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        let q = self.quad;
+        let quad_len = self.quad_len;
+        let padding_len = self.padding_len;
+        self.quad_len = 0;
+        self.padding_len = 0;
+        self.offset = 0;
+
+        if self.strict {
+            let expected_padding = if quad_len == 0 || !self.padding {
+                0
+            } else {
+                4 - quad_len
+            };
+            if padding_len != expected_padding {
+                return Err(DecodeError::InvalidPadding);
+            }
+        }
+        match quad_len {
+            0 => {}
+            1 => {
+                if self.strict {
+                    return Err(DecodeError::InvalidLength);
+                }
+            }
+            2 => {
+                if self.strict && q[1] & 0x0f != 0 {
+                    return Err(DecodeError::TrailingBits);
+                }
+                out.push((q[0] << 2) | (q[1] >> 4));
+            }
+            _ => {
+                if self.strict && q[2] & 0x03 != 0 {
+                    return Err(DecodeError::TrailingBits);
+                }
+                out.push((q[0] << 2) | (q[1] >> 4));
+                out.push((q[1] << 4) | (q[2] >> 2));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encodes `input` in one go.
+pub fn encode_bytes(input: &[u8], alphabet: Alphabet, padding: bool) -> String {
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    let mut encoder = Encoder::new(alphabet, padding);
+    encoder.update(input, &mut out);
+    encoder.finish(&mut out);
+    out
+}
+
+/// Decodes `input` in one go.
+pub fn decode_bytes(
+    input: &[u8],
+    alphabet: Alphabet,
+    padding: bool,
+    strict: bool,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut out = Vec::with_capacity(input.len() / 4 * 3 + 2);
+    let mut decoder = Decoder::new(alphabet, padding, strict);
+    decoder.update(input, &mut out)?;
+    decoder.finish(&mut out)?;
+    Ok(out)
+}
+
+/// Encodes `input` as unpadded base64url, the form used for Arweave IDs.
+pub fn encode_url(input: &[u8]) -> String {
+    encode_bytes(input, Alphabet::UrlSafe, false)
+}
+
+/// Strictly decodes unpadded base64url, the form used for Arweave IDs.
+pub fn decode_url(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    decode_bytes(input, Alphabet::UrlSafe, false, true)
+}
+
+/// Options accepted from Lua.
+struct Options {
+    alphabet: Alphabet,
+    padding: bool,
+    strict: bool,
+}
+
+fn parse_options(options: Option<LuaTable>) -> LuaResult<Options> {
+    let options = match options {
+        Some(options) => options,
+        None => {
+            return Ok(Options {
+                alphabet: Alphabet::Standard,
+                padding: true,
+                strict: false,
+            })
+        }
+    };
+    let alphabet = match options.get::<Option<String>>("alphabet")?.as_deref() {
+        None | Some("standard") => Alphabet::Standard,
+        Some("url") => Alphabet::UrlSafe,
+        Some(other) => {
+            return Err(LuaError::RuntimeError(format!(
+                "unknown base64 alphabet '{}', expected 'standard' or 'url'",
+                other
+            )))
+        }
+    };
+    let padding: Option<bool> = options.get("padding")?;
+    let strict: Option<bool> = options.get("strict")?;
+    Ok(Options {
+        alphabet,
+        padding: padding.unwrap_or_else(|| alphabet.default_padding()),
+        strict: strict.unwrap_or(false),
+    })
+}
+
+fn encode(_lua: &Lua, (data, options): (LuaString, Option<LuaTable>)) -> LuaResult<String> {
+    let options = parse_options(options)?;
+    Ok(encode_bytes(
+        &data.as_bytes(),
+        options.alphabet,
+        options.padding,
+    ))
+}
+
+fn decode(lua: &Lua, (data, options): (LuaString, Option<LuaTable>)) -> LuaResult<LuaString> {
+    let options = parse_options(options)?;
+    let decoded = decode_bytes(
+        &data.as_bytes(),
+        options.alphabet,
+        options.padding,
+        options.strict,
+    )?;
+    lua.create_string(decoded)
+}
+
+impl LuaUserData for Encoder {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("update", |_, this, chunk: LuaString| {
+            let mut out = String::new();
+            this.update(&chunk.as_bytes(), &mut out);
+            Ok(out)
+        });
+        methods.add_method_mut("finish", |_, this, ()| {
+            let mut out = String::new();
+            this.finish(&mut out);
+            Ok(out)
+        });
+    }
+}
+
+impl LuaUserData for Decoder {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("update", |lua, this, chunk: LuaString| {
+            let mut out = Vec::new();
+            this.update(&chunk.as_bytes(), &mut out)?;
+            lua.create_string(out)
+        });
+        methods.add_method_mut("finish", |lua, this, ()| {
+            let mut out = Vec::new();
+            this.finish(&mut out)?;
+            lua.create_string(out)
+        });
+    }
+}
+
+fn encoder(_lua: &Lua, options: Option<LuaTable>) -> LuaResult<Encoder> {
+    let options = parse_options(options)?;
+    Ok(Encoder::new(options.alphabet, options.padding))
+}
+
+fn decoder(_lua: &Lua, options: Option<LuaTable>) -> LuaResult<Decoder> {
+    let options = parse_options(options)?;
+    Ok(Decoder::new(
+        options.alphabet,
+        options.padding,
+        options.strict,
+    ))
+}
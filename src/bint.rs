@@ -0,0 +1,1042 @@
+use super::*;
+use alloc::vec;
+use core::cmp::Ordering;
+
+const VERSION: &str = "0.5.1";
+
+/// Registers the `bint` module with Lua. Like the Lua `bint` library, the module is a
+/// constructor taking the integer width in bits, `require('.bint')(256)`, and returning
+/// a callable class table.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "bint"))]
+pub fn bint(lua: &Lua) -> LuaResult<LuaFunction> {
+    lua.create_function(|lua, bits: Option<usize>| new_class(lua, bits.unwrap_or(256)))
+}
+
+/// A fixed-width two's complement integer stored as little-endian 32-bit limbs.
+/// All arithmetic wraps around at the width, matching the Lua `bint` library;
+/// the `overflowing_*` and `checked_*` variants report when that happens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bint {
+    limbs: Vec<u32>,
+}
+
+impl Bint {
+    pub fn zero(bits: usize) -> Self {
+        Bint {
+            limbs: vec![0; bits / 32],
+        }
+    }
+
+    pub fn from_u64(value: u64, bits: usize) -> Self {
+        let mut result = Bint::zero(bits);
+        result.limbs[0] = value as u32;
+        if result.limbs.len() > 1 {
+            result.limbs[1] = (value >> 32) as u32;
+        }
+        result
+    }
+
+    pub fn from_i64(value: i64, bits: usize) -> Self {
+        let mut result = Bint::from_u64(value as u64, bits);
+        if value < 0 {
+            for limb in result.limbs.iter_mut().skip(2) {
+                *limb = u32::MAX;
+            }
+        }
+        result
+    }
+
+    /// Converts an integral float, returning `None` for fractions, infinities and NaN.
+    pub fn from_f64(value: f64, bits: usize) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        let raw = value.to_bits() & !(1 << 63);
+        let exponent = ((raw >> 52) & 0x7ff) as i64;
+        if exponent == 0 {
+            // Zero or subnormal; only zero is integral
+            return if raw == 0 {
+                Some(Bint::zero(bits))
+            } else {
+                None
+            };
+        }
+        let mantissa = (raw & ((1 << 52) - 1)) | (1 << 52);
+        let shift = exponent - 1075;
+        let magnitude = if shift >= 0 {
+            Bint::from_u64(mantissa, bits).shl(shift as usize)
+        } else {
+            let shift = (-shift) as u32;
+            if shift >= 64 || mantissa & ((1u64 << shift) - 1) != 0 {
+                return None;
+            }
+            Bint::from_u64(mantissa >> shift, bits)
+        };
+        Some(if value < 0.0 {
+            magnitude.wrapping_neg()
+        } else {
+            magnitude
+        })
+    }
+
+    /// Parses digits in `base` (2 to 36) with an optional sign, wrapping on overflow.
+    pub fn from_base(text: &str, base: u32, bits: usize) -> Option<Self> {
+        if !(2..=36).contains(&base) {
+            return None;
+        }
+        let text = text.trim();
+        let (negative, digits) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        if digits.is_empty() {
+            return None;
+        }
+        let mut result = Bint::zero(bits);
+        for c in digits.chars() {
+            let digit = c.to_digit(base)?;
+            result.mul_small_add(base, digit);
+        }
+        Some(if negative {
+            result.wrapping_neg()
+        } else {
+            result
+        })
+    }
+
+    /// Parses decimal, `0x` hexadecimal or `0b` binary text.
+    pub fn from_str_auto(text: &str, bits: usize) -> Option<Self> {
+        let text = text.trim();
+        let (negative, unsigned) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let parsed = if let Some(hex) = unsigned
+            .strip_prefix("0x")
+            .or_else(|| unsigned.strip_prefix("0X"))
+        {
+            Bint::from_base(hex, 16, bits)?
+        } else if let Some(binary) = unsigned
+            .strip_prefix("0b")
+            .or_else(|| unsigned.strip_prefix("0B"))
+        {
+            Bint::from_base(binary, 2, bits)?
+        } else {
+            Bint::from_base(unsigned, 10, bits)?
+        };
+        Some(if negative {
+            parsed.wrapping_neg()
+        } else {
+            parsed
+        })
+    }
+
+    pub fn max_value(bits: usize) -> Self {
+        let mut result = Bint {
+            limbs: vec![u32::MAX; bits / 32],
+        };
+        *result.limbs.last_mut().unwrap() = u32::MAX >> 1;
+        result
+    }
+
+    pub fn min_value(bits: usize) -> Self {
+        let mut result = Bint::zero(bits);
+        *result.limbs.last_mut().unwrap() = 1 << 31;
+        result
+    }
+
+    pub fn bits(&self) -> usize {
+        self.limbs.len() * 32
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|&limb| limb == 0)
+    }
+
+    pub fn is_neg(&self) -> bool {
+        self.limbs.last().is_some_and(|&limb| limb >> 31 == 1)
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.limbs[0] & 1 == 1
+    }
+
+    pub fn abs(&self) -> Self {
+        if self.is_neg() {
+            self.wrapping_neg()
+        } else {
+            self.clone()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.abs();
+        let mut value = 0.0;
+        for &limb in magnitude.limbs.iter().rev() {
+            value = value * 4294967296.0 + limb as f64;
+        }
+        if self.is_neg() {
+            -value
+        } else {
+            value
+        }
+    }
+
+    /// Converts to `i64` when the value fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        let fill = if self.is_neg() { u32::MAX } else { 0 };
+        if self.limbs.iter().skip(2).any(|&limb| limb != fill) {
+            return None;
+        }
+        let low = self.limbs[0] as u64 | (self.limbs.get(1).copied().unwrap_or(fill) as u64) << 32;
+        let value = low as i64;
+        // The sign of the truncated value must agree with the full value
+        if (value < 0) != self.is_neg() {
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Formats the value in `base` (2 to 36), as a signed or unsigned integer.
+    pub fn to_base(&self, base: u32, unsigned: bool) -> Option<String> {
+        if !(2..=36).contains(&base) {
+            return None;
+        }
+        let negative = !unsigned && self.is_neg();
+        let mut magnitude = if negative {
+            self.wrapping_neg()
+        } else {
+            self.clone()
+        };
+        let mut digits = Vec::new();
+        while !magnitude.is_zero() {
+            let digit = magnitude.div_small(base);
+            digits.push(char::from_digit(digit, base).unwrap_or('0'));
+        }
+        if digits.is_empty() {
+            digits.push('0');
+        }
+        if negative {
+            digits.push('-');
+        }
+        Some(digits.iter().rev().collect())
+    }
+
+    pub fn overflowing_add(&self, rhs: &Self) -> (Self, bool) {
+        let mut result = self.clone();
+        let mut carry = 0u64;
+        for (limb, &r) in result.limbs.iter_mut().zip(rhs.limbs.iter()) {
+            let sum = *limb as u64 + r as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+        }
+        let overflow = self.is_neg() == rhs.is_neg() && result.is_neg() != self.is_neg();
+        (result, overflow)
+    }
+
+    pub fn overflowing_sub(&self, rhs: &Self) -> (Self, bool) {
+        let mut result = self.clone();
+        let mut borrow = 0i64;
+        for (limb, &r) in result.limbs.iter_mut().zip(rhs.limbs.iter()) {
+            let diff = *limb as i64 - r as i64 - borrow;
+            *limb = diff as u32;
+            borrow = if diff < 0 { 1 } else { 0 };
+        }
+        let overflow = self.is_neg() != rhs.is_neg() && result.is_neg() != self.is_neg();
+        (result, overflow)
+    }
+
+    pub fn overflowing_mul(&self, rhs: &Self) -> (Self, bool) {
+        let n = self.limbs.len();
+        let product = mul_limbs(&self.abs().limbs, &rhs.abs().limbs);
+        let mut magnitude = Bint {
+            limbs: product[..n].to_vec(),
+        };
+        let high_set = product[n..].iter().any(|&limb| limb != 0);
+        let negative = self.is_neg() != rhs.is_neg() && !self.is_zero() && !rhs.is_zero();
+        // The magnitude may reach 2^(bits-1) only when the result is negative
+        let overflow = high_set
+            || (magnitude.is_neg() && !(negative && magnitude == Bint::min_value(self.bits())));
+        if negative {
+            magnitude = magnitude.wrapping_neg();
+        }
+        (magnitude, overflow)
+    }
+
+    pub fn wrapping_add(&self, rhs: &Self) -> Self {
+        self.overflowing_add(rhs).0
+    }
+
+    pub fn wrapping_sub(&self, rhs: &Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    pub fn wrapping_mul(&self, rhs: &Self) -> Self {
+        let n = self.limbs.len();
+        let product = mul_limbs(&self.limbs, &rhs.limbs);
+        Bint {
+            limbs: product[..n].to_vec(),
+        }
+    }
+
+    pub fn wrapping_neg(&self) -> Self {
+        let inverted = self.bnot();
+        inverted.wrapping_add(&Bint::from_u64(1, self.bits()))
+    }
+
+    pub fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        match self.overflowing_add(rhs) {
+            (result, false) => Some(result),
+            _ => None,
+        }
+    }
+
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        match self.overflowing_sub(rhs) {
+            (result, false) => Some(result),
+            _ => None,
+        }
+    }
+
+    pub fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        match self.overflowing_mul(rhs) {
+            (result, false) => Some(result),
+            _ => None,
+        }
+    }
+
+    /// Unsigned division, returning `None` when dividing by zero.
+    pub fn udivmod(&self, rhs: &Self) -> Option<(Self, Self)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let bits = self.bits();
+        let mut quotient = Bint::zero(bits);
+        let mut remainder = Bint::zero(bits);
+        for i in (0..bits).rev() {
+            let carry = remainder.is_neg();
+            remainder = remainder.shl(1);
+            if self.bit(i) {
+                remainder.limbs[0] |= 1;
+            }
+            // `carry` means the shifted remainder exceeds the width, so it's above rhs
+            if carry || remainder.cmp_unsigned(rhs) != Ordering::Less {
+                remainder = remainder.wrapping_sub(rhs);
+                quotient.limbs[i / 32] |= 1 << (i % 32);
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    /// Signed division truncating toward zero.
+    pub fn tdivmod(&self, rhs: &Self) -> Option<(Self, Self)> {
+        let (quotient, remainder) = self.abs().udivmod(&rhs.abs())?;
+        let quotient = if self.is_neg() != rhs.is_neg() {
+            quotient.wrapping_neg()
+        } else {
+            quotient
+        };
+        let remainder = if self.is_neg() {
+            remainder.wrapping_neg()
+        } else {
+            remainder
+        };
+        Some((quotient, remainder))
+    }
+
+    /// Signed division rounding toward negative infinity, like Lua's `//` and `%`.
+    pub fn idivmod(&self, rhs: &Self) -> Option<(Self, Self)> {
+        let (quotient, remainder) = self.tdivmod(rhs)?;
+        if !remainder.is_zero() && remainder.is_neg() != rhs.is_neg() {
+            let one = Bint::from_u64(1, self.bits());
+            Some((quotient.wrapping_sub(&one), remainder.wrapping_add(rhs)))
+        } else {
+            Some((quotient, remainder))
+        }
+    }
+
+    /// Raises to a non-negative power, wrapping on overflow.
+    pub fn pow(&self, exponent: &Self) -> Self {
+        let mut result = Bint::from_u64(1, self.bits());
+        let mut base = self.clone();
+        for i in 0..exponent.bits() {
+            if exponent.bit(i) {
+                result = result.wrapping_mul(&base);
+            }
+            base = base.wrapping_mul(&base);
+        }
+        result
+    }
+
+    /// Computes `self ^ exponent % modulus` treating all operands as unsigned.
+    pub fn upowmod(&self, exponent: &Self, modulus: &Self) -> Option<Self> {
+        let (_, mut base) = self.udivmod(modulus)?;
+        let mut result = Bint::from_u64(1, self.bits()).udivmod(modulus)?.1;
+        for i in 0..exponent.bits() {
+            if exponent.bit(i) {
+                result = mulmod(&result, &base, modulus);
+            }
+            base = mulmod(&base, &base, modulus);
+        }
+        Some(result)
+    }
+
+    pub fn shl(&self, n: usize) -> Self {
+        let mut result = Bint::zero(self.bits());
+        let (limb_shift, bit_shift) = (n / 32, n % 32);
+        for i in (limb_shift..self.limbs.len()).rev() {
+            let mut limb = self.limbs[i - limb_shift] << bit_shift;
+            if bit_shift > 0 && i > limb_shift {
+                limb |= self.limbs[i - limb_shift - 1] >> (32 - bit_shift);
+            }
+            result.limbs[i] = limb;
+        }
+        result
+    }
+
+    /// Logical right shift.
+    pub fn shr(&self, n: usize) -> Self {
+        let mut result = Bint::zero(self.bits());
+        let (limb_shift, bit_shift) = (n / 32, n % 32);
+        let len = self.limbs.len();
+        for i in 0..len.saturating_sub(limb_shift) {
+            let mut limb = self.limbs[i + limb_shift] >> bit_shift;
+            if bit_shift > 0 && i + limb_shift + 1 < len {
+                limb |= self.limbs[i + limb_shift + 1] << (32 - bit_shift);
+            }
+            result.limbs[i] = limb;
+        }
+        result
+    }
+
+    pub fn band(&self, rhs: &Self) -> Self {
+        self.zip_limbs(rhs, |a, b| a & b)
+    }
+
+    pub fn bor(&self, rhs: &Self) -> Self {
+        self.zip_limbs(rhs, |a, b| a | b)
+    }
+
+    pub fn bxor(&self, rhs: &Self) -> Self {
+        self.zip_limbs(rhs, |a, b| a ^ b)
+    }
+
+    pub fn bnot(&self) -> Self {
+        Bint {
+            limbs: self.limbs.iter().map(|limb| !limb).collect(),
+        }
+    }
+
+    pub fn cmp_signed(&self, rhs: &Self) -> Ordering {
+        match (self.is_neg(), rhs.is_neg()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => self.cmp_unsigned(rhs),
+        }
+    }
+
+    pub fn cmp_unsigned(&self, rhs: &Self) -> Ordering {
+        self.limbs.iter().rev().cmp(rhs.limbs.iter().rev())
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.limbs[i / 32] >> (i % 32) & 1 == 1
+    }
+
+    fn zip_limbs(&self, rhs: &Self, op: impl Fn(u32, u32) -> u32) -> Self {
+        Bint {
+            limbs: self
+                .limbs
+                .iter()
+                .zip(rhs.limbs.iter())
+                .map(|(&a, &b)| op(a, b))
+                .collect(),
+        }
+    }
+
+    /// `self = self * factor + addend`, wrapping at the width.
+    fn mul_small_add(&mut self, factor: u32, addend: u32) {
+        let mut carry = addend as u64;
+        for limb in self.limbs.iter_mut() {
+            let value = *limb as u64 * factor as u64 + carry;
+            *limb = value as u32;
+            carry = value >> 32;
+        }
+    }
+
+    /// Divides the unsigned value in place by `divisor`, returning the remainder.
+    fn div_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = (remainder << 32) | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        remainder as u32
+    }
+}
+
+/// Full schoolbook product of two equal-length limb slices.
+fn mul_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let value = product[i + j] as u64 + x as u64 * y as u64 + carry;
+            product[i + j] = value as u32;
+            carry = value >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+    product
+}
+
+/// `a * b % m` without losing the high half of the product.
+fn mulmod(a: &Bint, b: &Bint, m: &Bint) -> Bint {
+    let wide = Bint {
+        limbs: mul_limbs(&a.limbs, &b.limbs),
+    };
+    let mut wide_modulus = m.limbs.clone();
+    wide_modulus.resize(wide.limbs.len(), 0);
+    let (_, remainder) = wide
+        .udivmod(&Bint {
+            limbs: wide_modulus,
+        })
+        .unwrap_or_default();
+    Bint {
+        limbs: remainder.limbs[..a.limbs.len()].to_vec(),
+    }
+}
+
+impl Default for Bint {
+    fn default() -> Self {
+        Bint::zero(32)
+    }
+}
+
+impl LuaUserData for Bint {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("bits", |_, this| Ok(this.bits()));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("tostring", |_, this, ()| Ok(this.to_base(10, false)));
+        methods.add_method("tonumber", |_, this, ()| Ok(this.to_f64()));
+        methods.add_method("iszero", |_, this, ()| Ok(this.is_zero()));
+        methods.add_method("isneg", |_, this, ()| Ok(this.is_neg()));
+        methods.add_method("abs", |_, this, ()| Ok(this.abs()));
+
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(this.to_base(10, false))
+        });
+        methods.add_meta_method(LuaMetaMethod::Unm, |_, this, ()| Ok(this.wrapping_neg()));
+        methods.add_meta_method(LuaMetaMethod::BNot, |_, this, ()| Ok(this.bnot()));
+
+        methods.add_meta_function(LuaMetaMethod::Add, |lua, (a, b): (LuaValue, LuaValue)| {
+            arith(lua, &a, &b, |x, y| Ok(x.wrapping_add(y)), |x, y| x + y)
+        });
+        methods.add_meta_function(LuaMetaMethod::Sub, |lua, (a, b): (LuaValue, LuaValue)| {
+            arith(lua, &a, &b, |x, y| Ok(x.wrapping_sub(y)), |x, y| x - y)
+        });
+        methods.add_meta_function(LuaMetaMethod::Mul, |lua, (a, b): (LuaValue, LuaValue)| {
+            arith(lua, &a, &b, |x, y| Ok(x.wrapping_mul(y)), |x, y| x * y)
+        });
+        methods.add_meta_function(LuaMetaMethod::IDiv, |lua, (a, b): (LuaValue, LuaValue)| {
+            arith(
+                lua,
+                &a,
+                &b,
+                |x, y| match x.idivmod(y) {
+                    Some((quotient, _)) => Ok(quotient),
+                    None => Err(LuaError::RuntimeError(
+                        "attempt to perform 'n//0'".to_string(),
+                    )),
+                },
+                |x, y| floor(x / y),
+            )
+        });
+        methods.add_meta_function(LuaMetaMethod::Mod, |lua, (a, b): (LuaValue, LuaValue)| {
+            arith(
+                lua,
+                &a,
+                &b,
+                |x, y| match x.idivmod(y) {
+                    Some((_, remainder)) => Ok(remainder),
+                    None => Err(LuaError::RuntimeError(
+                        "attempt to perform 'n%0'".to_string(),
+                    )),
+                },
+                |x, y| x - floor(x / y) * y,
+            )
+        });
+        methods.add_meta_function(LuaMetaMethod::Div, |_, (a, b): (LuaValue, LuaValue)| {
+            Ok(to_number(&a)? / to_number(&b)?)
+        });
+        methods.add_meta_function(LuaMetaMethod::Pow, |lua, (a, b): (LuaValue, LuaValue)| {
+            pow(lua, &a, &b)
+        });
+        methods.add_meta_function(LuaMetaMethod::BAnd, |_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.band(&y))
+        });
+        methods.add_meta_function(LuaMetaMethod::BOr, |_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.bor(&y))
+        });
+        methods.add_meta_function(LuaMetaMethod::BXor, |_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.bxor(&y))
+        });
+        methods.add_meta_function(LuaMetaMethod::Shl, |_, (a, n): (LuaValue, i64)| {
+            let x = int_operand(&a, None)?;
+            Ok(shift(&x, n))
+        });
+        methods.add_meta_function(LuaMetaMethod::Shr, |_, (a, n): (LuaValue, i64)| {
+            let x = int_operand(&a, None)?;
+            Ok(shift(&x, -n))
+        });
+        methods.add_meta_function(LuaMetaMethod::Eq, |_, (a, b): (LuaValue, LuaValue)| {
+            Ok(compare(&a, &b)? == Ordering::Equal)
+        });
+        methods.add_meta_function(LuaMetaMethod::Lt, |_, (a, b): (LuaValue, LuaValue)| {
+            Ok(compare(&a, &b)? == Ordering::Less)
+        });
+        methods.add_meta_function(LuaMetaMethod::Le, |_, (a, b): (LuaValue, LuaValue)| {
+            Ok(compare(&a, &b)? != Ordering::Greater)
+        });
+    }
+}
+
+/// Builds the class table for integers of the given width.
+fn new_class(lua: &Lua, bits: usize) -> LuaResult<LuaTable> {
+    if bits < 64 || bits % 32 != 0 {
+        return Err(LuaError::RuntimeError(
+            "bitsize must be a multiple of 32 and at least 64".to_string(),
+        ));
+    }
+    let class = lua.create_table()?;
+    class.set("_version", VERSION)?;
+    class.set("bits", bits)?;
+
+    let new = lua.create_function(move |_, value: LuaValue| int_operand(&value, Some(bits)))?;
+    class.set("new", new.clone())?;
+    class.set("tobint", new.clone())?;
+
+    class.set(
+        "zero",
+        lua.create_function(move |_, ()| Ok(Bint::zero(bits)))?,
+    )?;
+    class.set(
+        "one",
+        lua.create_function(move |_, ()| Ok(Bint::from_u64(1, bits)))?,
+    )?;
+    class.set(
+        "maxinteger",
+        lua.create_function(move |_, ()| Ok(Bint::max_value(bits)))?,
+    )?;
+    class.set(
+        "mininteger",
+        lua.create_function(move |_, ()| Ok(Bint::min_value(bits)))?,
+    )?;
+    class.set(
+        "frominteger",
+        lua.create_function(move |_, value: LuaValue| match value {
+            LuaValue::Integer(i) => Ok(Some(Bint::from_i64(i, bits))),
+            LuaValue::Number(n) => Ok(Bint::from_f64(trunc(n), bits)),
+            _ => Ok(None),
+        })?,
+    )?;
+    class.set(
+        "fromuinteger",
+        lua.create_function(move |_, value: i64| Ok(Bint::from_u64(value as u64, bits)))?,
+    )?;
+    class.set(
+        "fromstring",
+        lua.create_function(move |_, text: String| Ok(Bint::from_str_auto(&text, bits)))?,
+    )?;
+    class.set(
+        "frombase",
+        lua.create_function(move |_, (text, base): (String, Option<u32>)| {
+            Ok(Bint::from_base(&text, base.unwrap_or(10), bits))
+        })?,
+    )?;
+
+    class.set(
+        "tobase",
+        lua.create_function(
+            |_, (value, base, unsigned): (LuaValue, Option<u32>, Option<bool>)| {
+                let x = int_operand(&value, None)?;
+                let base = base.unwrap_or(10);
+                Ok(x.to_base(base, unsigned.unwrap_or(base != 10)))
+            },
+        )?,
+    )?;
+    class.set(
+        "tostring",
+        lua.create_function(
+            |_, value: LuaValue| Ok(int_operand(&value, None)?.to_base(10, false)),
+        )?,
+    )?;
+    class.set(
+        "tonumber",
+        lua.create_function(|_, value: LuaValue| to_number(&value))?,
+    )?;
+    class.set(
+        "tointeger",
+        lua.create_function(|_, value: LuaValue| Ok(int_operand(&value, None)?.to_i64()))?,
+    )?;
+
+    class.set(
+        "isbint",
+        lua.create_function(|_, value: LuaValue| {
+            Ok(matches!(value, LuaValue::UserData(ud) if ud.is::<Bint>()))
+        })?,
+    )?;
+    class.set(
+        "iszero",
+        lua.create_function(|_, value: LuaValue| Ok(int_operand(&value, None)?.is_zero()))?,
+    )?;
+    class.set(
+        "isone",
+        lua.create_function(|_, value: LuaValue| {
+            Ok(int_operand(&value, None)?.to_i64() == Some(1))
+        })?,
+    )?;
+    class.set(
+        "isneg",
+        lua.create_function(|_, value: LuaValue| Ok(int_operand(&value, None)?.is_neg()))?,
+    )?;
+    class.set(
+        "ispos",
+        lua.create_function(|_, value: LuaValue| {
+            let x = int_operand(&value, None)?;
+            Ok(!x.is_neg() && !x.is_zero())
+        })?,
+    )?;
+    class.set(
+        "iseven",
+        lua.create_function(|_, value: LuaValue| Ok(!int_operand(&value, None)?.is_odd()))?,
+    )?;
+    class.set(
+        "isodd",
+        lua.create_function(|_, value: LuaValue| Ok(int_operand(&value, None)?.is_odd()))?,
+    )?;
+    class.set(
+        "abs",
+        lua.create_function(|_, value: LuaValue| Ok(int_operand(&value, None)?.abs()))?,
+    )?;
+
+    class.set(
+        "eq",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            Ok(compare(&a, &b)? == Ordering::Equal)
+        })?,
+    )?;
+    class.set(
+        "lt",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            Ok(compare(&a, &b)? == Ordering::Less)
+        })?,
+    )?;
+    class.set(
+        "le",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            Ok(compare(&a, &b)? != Ordering::Greater)
+        })?,
+    )?;
+    class.set(
+        "ult",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.cmp_unsigned(&y) == Ordering::Less)
+        })?,
+    )?;
+    class.set(
+        "ule",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.cmp_unsigned(&y) != Ordering::Greater)
+        })?,
+    )?;
+    class.set(
+        "max",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(if x.cmp_signed(&y) == Ordering::Less {
+                y
+            } else {
+                x
+            })
+        })?,
+    )?;
+    class.set(
+        "min",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(if y.cmp_signed(&x) == Ordering::Less {
+                y
+            } else {
+                x
+            })
+        })?,
+    )?;
+
+    class.set(
+        "add",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.wrapping_add(&y))
+        })?,
+    )?;
+    class.set(
+        "sub",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.wrapping_sub(&y))
+        })?,
+    )?;
+    class.set(
+        "mul",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.wrapping_mul(&y))
+        })?,
+    )?;
+    class.set(
+        "checkedadd",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.checked_add(&y))
+        })?,
+    )?;
+    class.set(
+        "checkedsub",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.checked_sub(&y))
+        })?,
+    )?;
+    class.set(
+        "checkedmul",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(x.checked_mul(&y))
+        })?,
+    )?;
+
+    class.set(
+        "udivmod",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            division(x.udivmod(&y))
+        })?,
+    )?;
+    class.set(
+        "udiv",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(division(x.udivmod(&y))?.0)
+        })?,
+    )?;
+    class.set(
+        "umod",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(division(x.udivmod(&y))?.1)
+        })?,
+    )?;
+    class.set(
+        "tdivmod",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            division(x.tdivmod(&y))
+        })?,
+    )?;
+    class.set(
+        "tdiv",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(division(x.tdivmod(&y))?.0)
+        })?,
+    )?;
+    class.set(
+        "tmod",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            Ok(division(x.tdivmod(&y))?.1)
+        })?,
+    )?;
+    class.set(
+        "idivmod",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            division(x.idivmod(&y))
+        })?,
+    )?;
+    class.set(
+        "ipow",
+        lua.create_function(|_, (a, b): (LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            if y.is_neg() {
+                return Err(LuaError::RuntimeError(
+                    "ipow exponent must be non-negative".to_string(),
+                ));
+            }
+            Ok(x.pow(&y))
+        })?,
+    )?;
+    class.set(
+        "upowmod",
+        lua.create_function(|_, (a, b, m): (LuaValue, LuaValue, LuaValue)| {
+            let (x, y) = int_operands(&a, &b)?;
+            let m = int_operand(&m, Some(x.bits()))?;
+            match x.upowmod(&y, &m) {
+                Some(result) => Ok(result),
+                None => Err(LuaError::RuntimeError(
+                    "attempt to perform 'n%0'".to_string(),
+                )),
+            }
+        })?,
+    )?;
+
+    // Calling the class constructs a value, like `bint(5)`
+    let metatable = lua.create_table()?;
+    metatable.set(
+        "__call",
+        lua.create_function(move |_, (_, value): (LuaValue, LuaValue)| {
+            int_operand(&value, Some(bits))
+        })?,
+    )?;
+    class.set_metatable(Some(metatable));
+
+    Ok(class)
+}
+
+/// Converts a bint, integer, integral float or numeric string into a bint. The width
+/// comes from `bits`, or from the value itself when it is already a bint.
+fn int_operand(value: &LuaValue, bits: Option<usize>) -> LuaResult<Bint> {
+    let converted = match value {
+        LuaValue::UserData(ud) if ud.is::<Bint>() => {
+            let x = ud.borrow::<Bint>()?.clone();
+            match bits {
+                Some(bits) if bits != x.bits() => {
+                    return Err(LuaError::RuntimeError(format!(
+                        "cannot mix a {}-bit bint with a {}-bit bint",
+                        x.bits(),
+                        bits
+                    )))
+                }
+                _ => Some(x),
+            }
+        }
+        LuaValue::Integer(i) => Some(Bint::from_i64(*i, bits.unwrap_or(64))),
+        LuaValue::Number(n) => Bint::from_f64(*n, bits.unwrap_or(64)),
+        LuaValue::String(s) => Bint::from_str_auto(&s.to_str()?, bits.unwrap_or(64)),
+        _ => None,
+    };
+    converted.ok_or_else(|| {
+        LuaError::RuntimeError(format!(
+            "value of type '{}' cannot be converted to bint",
+            value.type_name()
+        ))
+    })
+}
+
+/// Converts a pair of operands, taking the width from whichever one is a bint.
+fn int_operands(a: &LuaValue, b: &LuaValue) -> LuaResult<(Bint, Bint)> {
+    let bits = [a, b].iter().find_map(|value| match value {
+        LuaValue::UserData(ud) => ud.borrow::<Bint>().ok().map(|x| x.bits()),
+        _ => None,
+    });
+    Ok((int_operand(a, bits)?, int_operand(b, bits)?))
+}
+
+fn is_fractional(value: &LuaValue) -> bool {
+    matches!(value, LuaValue::Number(n) if trunc(*n) != *n || !n.is_finite())
+}
+
+/// Rounds toward zero; `core` has no float rounding without `std`.
+fn trunc(n: f64) -> f64 {
+    // Floats this large have no fractional bits
+    if !n.is_finite() || n >= 4503599627370496.0 || n <= -4503599627370496.0 {
+        n
+    } else {
+        n as i64 as f64
+    }
+}
+
+fn floor(n: f64) -> f64 {
+    let t = trunc(n);
+    if t > n {
+        t - 1.0
+    } else {
+        t
+    }
+}
+
+fn to_number(value: &LuaValue) -> LuaResult<f64> {
+    match value {
+        LuaValue::Number(n) => Ok(*n),
+        _ => Ok(int_operand(value, None)?.to_f64()),
+    }
+}
+
+/// Applies an integer operation, falling back to float arithmetic when either operand
+/// is a fractional number, as the Lua `bint` library does.
+fn arith(
+    lua: &Lua,
+    a: &LuaValue,
+    b: &LuaValue,
+    int_op: impl Fn(&Bint, &Bint) -> LuaResult<Bint>,
+    float_op: impl Fn(f64, f64) -> f64,
+) -> LuaResult<LuaValue> {
+    if is_fractional(a) || is_fractional(b) {
+        return Ok(LuaValue::Number(float_op(to_number(a)?, to_number(b)?)));
+    }
+    let (x, y) = int_operands(a, b)?;
+    Ok(LuaValue::UserData(lua.create_userdata(int_op(&x, &y)?)?))
+}
+
+fn pow(lua: &Lua, a: &LuaValue, b: &LuaValue) -> LuaResult<LuaValue> {
+    if is_fractional(b) {
+        return Err(LuaError::RuntimeError(
+            "bint exponent must be an integer".to_string(),
+        ));
+    }
+    let (x, y) = int_operands(a, b)?;
+    if y.is_neg() {
+        // Negative exponents produce a fraction
+        let denominator = x.pow(&y.wrapping_neg()).to_f64();
+        return Ok(LuaValue::Number(1.0 / denominator));
+    }
+    Ok(LuaValue::UserData(lua.create_userdata(x.pow(&y))?))
+}
+
+fn shift(x: &Bint, n: i64) -> Bint {
+    let amount = n.unsigned_abs() as usize;
+    if amount >= x.bits() {
+        Bint::zero(x.bits())
+    } else if n >= 0 {
+        x.shl(amount)
+    } else {
+        x.shr(amount)
+    }
+}
+
+/// Signed comparison, comparing as floats when either side is a fractional number.
+fn compare(a: &LuaValue, b: &LuaValue) -> LuaResult<Ordering> {
+    if is_fractional(a) || is_fractional(b) {
+        let (x, y) = (to_number(a)?, to_number(b)?);
+        return Ok(x.partial_cmp(&y).unwrap_or(Ordering::Greater));
+    }
+    let (x, y) = int_operands(a, b)?;
+    Ok(x.cmp_signed(&y))
+}
+
+fn division(result: Option<(Bint, Bint)>) -> LuaResult<(Bint, Bint)> {
+    result.ok_or_else(|| {
+        LuaError::RuntimeError("attempt to perform integer division by zero".to_string())
+    })
+}
@@ -0,0 +1,277 @@
+use super::*;
+use crate::crypto::sha256;
+use alloc::vec;
+
+const VERSION: &str = "0.1.0";
+
+/// Registry key holding the generator, so its state carries over between messages.
+const STATE_KEY: &str = "chance.state";
+
+const ALPHANUMERIC: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Registers the `chance` module with Lua: deterministic random numbers for processes,
+/// where `math.random` would make evaluation differ between nodes.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "chance"))]
+pub fn chance(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set(
+        "seed",
+        lua.create_function(|lua, seed: LuaValue| {
+            let twister = match seed {
+                LuaValue::Integer(i) => Twister::new(i as u32),
+                LuaValue::Number(n) => Twister::new(n as i64 as u32),
+                LuaValue::String(s) => Twister::from_bytes(&sha256(&s.as_bytes())),
+                _ => {
+                    return Err(LuaError::RuntimeError(
+                        "seed must be a number or a string".to_string(),
+                    ))
+                }
+            };
+            store(lua, twister)
+        })?,
+    )?;
+    exports.set(
+        "seedFromMessage",
+        lua.create_function(|lua, msg: LuaTable| seed_from_message(lua, &msg))?,
+    )?;
+    exports.set(
+        "integer",
+        lua.create_function(|lua, (min, max): (i64, i64)| {
+            with_twister(lua, |t| t.integer(min, max))?
+        })?,
+    )?;
+    exports.set(
+        "float",
+        lua.create_function(|lua, (min, max): (Option<f64>, Option<f64>)| {
+            let (min, max) = (min.unwrap_or(0.0), max.unwrap_or(1.0));
+            with_twister(lua, |t| min + t.float() * (max - min))
+        })?,
+    )?;
+    exports.set(
+        "random",
+        lua.create_function(|lua, (m, n): (Option<i64>, Option<i64>)| {
+            // Same argument forms as `math.random`
+            match (m, n) {
+                (None, _) => Ok(LuaValue::Number(with_twister(lua, |t| t.float())?)),
+                (Some(m), None) => Ok(LuaValue::Integer(with_twister(lua, |t| t.integer(1, m))??)),
+                (Some(m), Some(n)) => {
+                    Ok(LuaValue::Integer(with_twister(lua, |t| t.integer(m, n))??))
+                }
+            }
+        })?,
+    )?;
+    exports.set(
+        "pick",
+        lua.create_function(|lua, list: LuaTable| {
+            let len = list.raw_len() as i64;
+            if len == 0 {
+                return Ok(LuaValue::Nil);
+            }
+            let index = with_twister(lua, |t| t.integer(1, len))??;
+            list.raw_get(index)
+        })?,
+    )?;
+    exports.set(
+        "shuffle",
+        lua.create_function(|lua, list: LuaTable| {
+            let mut items = list
+                .sequence_values::<LuaValue>()
+                .collect::<LuaResult<Vec<_>>>()?;
+            with_twister(lua, |t| t.shuffle(&mut items))??;
+            lua.create_sequence_from(items)
+        })?,
+    )?;
+    exports.set(
+        "string",
+        lua.create_function(|lua, (length, charset): (usize, Option<String>)| {
+            let charset: Vec<char> = charset.as_deref().unwrap_or(ALPHANUMERIC).chars().collect();
+            if charset.is_empty() {
+                return Err(LuaError::RuntimeError(
+                    "charset must not be empty".to_string(),
+                ));
+            }
+            let last = charset.len() as i64 - 1;
+            with_twister(lua, |t| {
+                (0..length)
+                    .map(|_| Ok(charset[t.integer(0, last)? as usize]))
+                    .collect::<LuaResult<String>>()
+            })?
+        })?,
+    )?;
+    Ok(exports)
+}
+
+/// Reseeds the generator from the process id and the message `Timestamp` and
+/// `Block-Height`, so every node draws the same numbers for the same message.
+pub(crate) fn seed_from_message(lua: &Lua, msg: &LuaTable) -> LuaResult<()> {
+    let process_id = match lua.globals().get::<Option<LuaTable>>("ao")? {
+        Some(ao) => match ao.get::<Option<LuaTable>>("env")? {
+            Some(env) => env
+                .get::<Option<LuaTable>>("Process")?
+                .map(|process| process.get::<Option<String>>("Id"))
+                .transpose()?
+                .flatten(),
+            None => None,
+        },
+        None => None,
+    };
+    let timestamp: Option<String> = msg.get("Timestamp")?;
+    let height: Option<String> = msg.get("Block-Height")?;
+    let seed = format!(
+        "{}|{}|{}",
+        process_id.unwrap_or_default(),
+        timestamp.unwrap_or_default(),
+        height.unwrap_or_default()
+    );
+    store(lua, Twister::from_bytes(&sha256(seed.as_bytes())))
+}
+
+fn store(lua: &Lua, twister: Twister) -> LuaResult<()> {
+    lua.set_named_registry_value(STATE_KEY, lua.create_userdata(twister)?)
+}
+
+/// Runs `f` on the stored generator, creating one with the reference default seed
+/// if nothing has seeded it yet.
+fn with_twister<R>(lua: &Lua, f: impl FnOnce(&mut Twister) -> R) -> LuaResult<R> {
+    let state = match lua.named_registry_value::<Option<LuaAnyUserData>>(STATE_KEY)? {
+        Some(state) => state,
+        None => {
+            store(lua, Twister::new(5489))?;
+            lua.named_registry_value(STATE_KEY)?
+        }
+    };
+    let mut twister = state.borrow_mut::<Twister>()?;
+    Ok(f(&mut twister))
+}
+
+const N: usize = 624;
+const M: usize = 397;
+
+/// The 32-bit Mersenne Twister (MT19937).
+pub struct Twister {
+    state: Vec<u32>,
+    index: usize,
+}
+
+impl LuaUserData for Twister {}
+
+impl Twister {
+    pub fn new(seed: u32) -> Self {
+        let mut state = vec![0u32; N];
+        state[0] = seed;
+        for i in 1..N {
+            let prev = state[i - 1];
+            state[i] = 1812433253u32
+                .wrapping_mul(prev ^ (prev >> 30))
+                .wrapping_add(i as u32);
+        }
+        Twister { state, index: N }
+    }
+
+    /// Seeds from bytes using the reference `init_by_array`, reading big-endian words.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let key: Vec<u32> = bytes
+            .chunks(4)
+            .map(|chunk| {
+                let mut word = [0u8; 4];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u32::from_be_bytes(word)
+            })
+            .collect();
+        let mut twister = Twister::new(19650218);
+        let state = &mut twister.state;
+        let (mut i, mut j) = (1, 0);
+        for _ in 0..N.max(key.len()) {
+            let prev = state[i - 1];
+            state[i] = (state[i] ^ (prev ^ (prev >> 30)).wrapping_mul(1664525))
+                .wrapping_add(key.get(j).copied().unwrap_or(0))
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N - 1 {
+            let prev = state[i - 1];
+            state[i] =
+                (state[i] ^ (prev ^ (prev >> 30)).wrapping_mul(1566083941)).wrapping_sub(i as u32);
+            i += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+        }
+        state[0] = 0x80000000;
+        twister
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+        let mut y = self.state[self.index];
+        self.index += 1;
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c5680;
+        y ^= (y << 15) & 0xefc60000;
+        y ^ (y >> 18)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// A float in `[0, 1)` with 53 bits of randomness (`genrand_res53`).
+    pub fn float(&mut self) -> f64 {
+        let a = (self.next_u32() >> 5) as f64;
+        let b = (self.next_u32() >> 6) as f64;
+        (a * 67108864.0 + b) / 9007199254740992.0
+    }
+
+    /// An integer in `[min, max]` without modulo bias.
+    pub fn integer(&mut self, min: i64, max: i64) -> LuaResult<i64> {
+        if min > max {
+            return Err(LuaError::RuntimeError("interval is empty".to_string()));
+        }
+        let span = (max as i128 - min as i128 + 1) as u128;
+        if span > u64::MAX as u128 {
+            return Ok(self.next_u64() as i64);
+        }
+        let span = span as u64;
+        // Reject draws from the incomplete final block of `span` values
+        let limit = u64::MAX - u64::MAX % span;
+        loop {
+            let value = self.next_u64();
+            if value < limit {
+                return Ok((min as i128 + (value % span) as i128) as i64);
+            }
+        }
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) -> LuaResult<()> {
+        for i in (1..items.len()).rev() {
+            let j = self.integer(0, i as i64)? as usize;
+            items.swap(i, j);
+        }
+        Ok(())
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & 0x80000000) | (self.state[(i + 1) % N] & 0x7fffffff);
+            let mut next = self.state[(i + M) % N] ^ (y >> 1);
+            if y & 1 == 1 {
+                next ^= 0x9908b0df;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+}
@@ -0,0 +1,263 @@
+use super::*;
+
+mod bignum;
+mod blake2b;
+mod ed25519;
+mod rsa;
+mod secp256k1;
+mod sha2;
+mod sha3;
+
+pub use blake2b::blake2b;
+pub use ed25519::verify_ed25519;
+pub use rsa::verify_rsa_pss;
+pub use secp256k1::verify_secp256k1;
+pub use sha2::{sha256, sha384, sha512};
+pub use sha3::{keccak256, sha3_256, sha3_512};
+
+const VERSION: &str = "0.1.0";
+
+/// Registers the `crypto` module with Lua, mirroring the aos `crypto` API:
+/// `digest.*` functions return an object with `asHex`, `asBytes` and `asString`,
+/// and accept either a string or an array of bytes as input.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "crypto"))]
+pub fn crypto(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+
+    let digest = lua.create_table()?;
+    digest.set(
+        "sha2_256",
+        lua.create_function(|lua, data: LuaValue| {
+            digest_result(lua, sha256(&input_bytes(data)?).to_vec())
+        })?,
+    )?;
+    digest.set(
+        "sha2_384",
+        lua.create_function(|lua, data: LuaValue| {
+            digest_result(lua, sha384(&input_bytes(data)?).to_vec())
+        })?,
+    )?;
+    digest.set(
+        "sha2_512",
+        lua.create_function(|lua, data: LuaValue| {
+            digest_result(lua, sha512(&input_bytes(data)?).to_vec())
+        })?,
+    )?;
+    digest.set(
+        "sha3_256",
+        lua.create_function(|lua, data: LuaValue| {
+            digest_result(lua, sha3_256(&input_bytes(data)?))
+        })?,
+    )?;
+    digest.set(
+        "sha3_512",
+        lua.create_function(|lua, data: LuaValue| {
+            digest_result(lua, sha3_512(&input_bytes(data)?))
+        })?,
+    )?;
+    digest.set(
+        "keccak256",
+        lua.create_function(|lua, data: LuaValue| {
+            digest_result(lua, keccak256(&input_bytes(data)?))
+        })?,
+    )?;
+    digest.set(
+        "blake2b",
+        lua.create_function(
+            |lua, (data, out_len, key): (LuaValue, Option<usize>, Option<LuaValue>)| {
+                let key = match key {
+                    Some(key) => input_bytes(key)?,
+                    None => Vec::new(),
+                };
+                digest_result(
+                    lua,
+                    blake2b(&input_bytes(data)?, out_len.unwrap_or(64), &key)?,
+                )
+            },
+        )?,
+    )?;
+    digest.set("hmac", lua.create_function(create_hmac)?)?;
+    exports.set("digest", digest)?;
+
+    let mac = lua.create_table()?;
+    mac.set("createHmac", lua.create_function(create_hmac)?)?;
+    exports.set("mac", mac)?;
+
+    let stream = lua.create_table()?;
+    stream.set("fromString", lua.create_function(|_, s: LuaString| Ok(s))?)?;
+    stream.set(
+        "fromHex",
+        lua.create_function(|lua, hex: String| lua.create_string(from_hex(&hex)?))?,
+    )?;
+    stream.set(
+        "fromArray",
+        lua.create_function(|lua, bytes: LuaTable| {
+            lua.create_string(input_bytes(LuaValue::Table(bytes))?)
+        })?,
+    )?;
+    let hex = lua.create_table()?;
+    hex.set(
+        "stringToHex",
+        lua.create_function(|_, s: LuaString| Ok(to_hex(&s.as_bytes())))?,
+    )?;
+    hex.set(
+        "hexToString",
+        lua.create_function(|lua, hex: String| lua.create_string(from_hex(&hex)?))?,
+    )?;
+    let utils = lua.create_table()?;
+    utils.set("stream", stream)?;
+    utils.set("hex", hex)?;
+    exports.set("utils", utils)?;
+
+    Ok(exports)
+}
+
+/// Hash functions usable as the HMAC digest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha256,
+    Sha3_256,
+    Sha3_512,
+    Keccak256,
+}
+
+impl Algorithm {
+    pub fn from_name(name: &str) -> LuaResult<Self> {
+        match name {
+            "sha256" | "sha2_256" => Ok(Algorithm::Sha256),
+            "sha3_256" => Ok(Algorithm::Sha3_256),
+            "sha3_512" => Ok(Algorithm::Sha3_512),
+            "keccak256" => Ok(Algorithm::Keccak256),
+            _ => Err(LuaError::RuntimeError(format!(
+                "unsupported hash algorithm '{}'",
+                name
+            ))),
+        }
+    }
+
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Sha256 => sha256(data).to_vec(),
+            Algorithm::Sha3_256 => sha3_256(data),
+            Algorithm::Sha3_512 => sha3_512(data),
+            Algorithm::Keccak256 => keccak256(data),
+        }
+    }
+
+    fn block_size(self) -> usize {
+        match self {
+            Algorithm::Sha256 => 64,
+            Algorithm::Sha3_256 | Algorithm::Keccak256 => 136,
+            Algorithm::Sha3_512 => 72,
+        }
+    }
+}
+
+/// Computes HMAC (RFC 2104) of `data` under `key` with the given hash.
+pub fn hmac(algorithm: Algorithm, key: &[u8], data: &[u8]) -> Vec<u8> {
+    let block_size = algorithm.block_size();
+    let mut key = if key.len() > block_size {
+        algorithm.digest(key)
+    } else {
+        key.to_vec()
+    };
+    key.resize(block_size, 0);
+
+    let mut inner: Vec<u8> = key.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(data);
+    let mut outer: Vec<u8> = key.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(&algorithm.digest(&inner));
+    algorithm.digest(&outer)
+}
+
+fn create_hmac(
+    lua: &Lua,
+    (data, key, algorithm): (LuaValue, LuaValue, Option<String>),
+) -> LuaResult<LuaTable> {
+    let algorithm = Algorithm::from_name(algorithm.as_deref().unwrap_or("sha256"))?;
+    digest_result(
+        lua,
+        hmac(algorithm, &input_bytes(key)?, &input_bytes(data)?),
+    )
+}
+
+/// Wraps digest bytes in the aos result object. The accessors are called with a dot
+/// (`.asHex()`), so they ignore any arguments.
+fn digest_result(lua: &Lua, bytes: Vec<u8>) -> LuaResult<LuaTable> {
+    let result = lua.create_table()?;
+
+    let hex = to_hex(&bytes);
+    result.set(
+        "asHex",
+        lua.create_function(move |_, _: LuaMultiValue| Ok(hex.clone()))?,
+    )?;
+
+    let array = lua.create_table()?;
+    for byte in &bytes {
+        array.push(*byte)?;
+    }
+    result.set(
+        "asBytes",
+        lua.create_function(move |_, _: LuaMultiValue| Ok(array.clone()))?,
+    )?;
+
+    let string = lua.create_string(&bytes)?;
+    result.set(
+        "asString",
+        lua.create_function(move |_, _: LuaMultiValue| Ok(string.clone()))?,
+    )?;
+
+    Ok(result)
+}
+
+/// Reads digest input given either as a string or as an array of byte values.
+fn input_bytes(value: LuaValue) -> LuaResult<Vec<u8>> {
+    match value {
+        LuaValue::String(s) => Ok(s.as_bytes().to_vec()),
+        LuaValue::Table(t) => t
+            .sequence_values::<i64>()
+            .map(|byte| match byte? {
+                b @ 0..=255 => Ok(b as u8),
+                b => Err(LuaError::RuntimeError(format!(
+                    "byte value {} is out of range",
+                    b
+                ))),
+            })
+            .collect(),
+        _ => Err(LuaError::RuntimeError(
+            "input must be a string or an array of bytes".to_string(),
+        )),
+    }
+}
+
+/// Formats bytes as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push(DIGITS[(byte >> 4) as usize] as char);
+        out.push(DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    out
+}
+
+/// Parses a hex string (either case) into bytes.
+pub fn from_hex(hex: &str) -> LuaResult<Vec<u8>> {
+    let hex = hex.as_bytes();
+    if hex.len() % 2 != 0 {
+        return Err(LuaError::RuntimeError(
+            "hex string must have an even length".to_string(),
+        ));
+    }
+    hex.chunks_exact(2)
+        .map(|pair| {
+            let high = (pair[0] as char).to_digit(16);
+            let low = (pair[1] as char).to_digit(16);
+            match (high, low) {
+                (Some(high), Some(low)) => Ok((high * 16 + low) as u8),
+                _ => Err(LuaError::RuntimeError("invalid hex string".to_string())),
+            }
+        })
+        .collect()
+}
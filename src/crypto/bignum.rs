@@ -0,0 +1,251 @@
+use super::*;
+use alloc::vec;
+use core::cmp::Ordering;
+
+/// An odd modulus set up for Montgomery multiplication.
+///
+/// Values are little-endian `u64` limbs, exactly as many as the modulus has. The
+/// arithmetic methods take and return values in Montgomery form (`x * R mod n`, with
+/// `R = 2^(64 * limbs)`); `to_mont` and `to_plain` convert.
+#[derive(Clone, Debug)]
+pub struct Modulus {
+    n: Vec<u64>,
+    // -n^-1 mod 2^64
+    n0: u64,
+    // R^2 mod n
+    r2: Vec<u64>,
+}
+
+impl Modulus {
+    /// Reads a big-endian modulus, returning `None` when it is even or zero.
+    pub fn from_be_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut n = from_be_bytes(bytes);
+        while n.len() > 1 && n[n.len() - 1] == 0 {
+            n.pop();
+        }
+        if n[0] & 1 == 0 {
+            return None;
+        }
+
+        // Newton's iteration doubles the correct low bits of the inverse each step
+        let mut inverse = n[0];
+        for _ in 0..5 {
+            inverse = inverse.wrapping_mul(2u64.wrapping_sub(n[0].wrapping_mul(inverse)));
+        }
+
+        let mut modulus = Modulus {
+            n0: inverse.wrapping_neg(),
+            r2: Vec::new(),
+            n,
+        };
+        // Doubling 1 modulo n, 2 * 64 * limbs times, gives R^2 mod n
+        let mut r2 = modulus.zero();
+        r2[0] = 1;
+        for _ in 0..2 * 64 * modulus.n.len() {
+            modulus.double_in(&mut r2, false);
+        }
+        modulus.r2 = r2;
+        Some(modulus)
+    }
+
+    /// The number of limbs in a value.
+    pub fn limbs(&self) -> usize {
+        self.n.len()
+    }
+
+    /// The modulus's length in bits.
+    pub fn bits(&self) -> usize {
+        let top = self.n[self.n.len() - 1];
+        64 * self.n.len() - top.leading_zeros() as usize
+    }
+
+    pub fn zero(&self) -> Vec<u64> {
+        vec![0; self.n.len()]
+    }
+
+    /// One, in Montgomery form.
+    pub fn one(&self) -> Vec<u64> {
+        let mut one = self.zero();
+        one[0] = 1;
+        self.to_mont(&one)
+    }
+
+    /// Reduces a big-endian number of any length, returning it in plain form.
+    pub fn reduce(&self, bytes: &[u8]) -> Vec<u64> {
+        let mut value = self.zero();
+        for byte in bytes {
+            for i in (0..8).rev() {
+                self.double_in(&mut value, byte >> i & 1 == 1);
+            }
+        }
+        value
+    }
+
+    /// Whether a plain value, as limbs, is below the modulus.
+    pub fn contains(&self, value: &[u64]) -> bool {
+        compare(value, &self.n) == Ordering::Less
+    }
+
+    pub fn to_mont(&self, value: &[u64]) -> Vec<u64> {
+        self.mul(value, &self.r2)
+    }
+
+    pub fn to_plain(&self, value: &[u64]) -> Vec<u64> {
+        let mut one = self.zero();
+        one[0] = 1;
+        self.mul(value, &one)
+    }
+
+    /// `a * b / R mod n`, by coarsely integrated operand scanning.
+    pub fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let s = self.n.len();
+        let mut t = vec![0u64; s + 2];
+        for &bi in b {
+            let mut carry = 0u128;
+            for j in 0..s {
+                let value = t[j] as u128 + a[j] as u128 * bi as u128 + carry;
+                t[j] = value as u64;
+                carry = value >> 64;
+            }
+            let value = t[s] as u128 + carry;
+            t[s] = value as u64;
+            t[s + 1] = (value >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.n0);
+            let mut carry = (t[0] as u128 + m as u128 * self.n[0] as u128) >> 64;
+            for j in 1..s {
+                let value = t[j] as u128 + m as u128 * self.n[j] as u128 + carry;
+                t[j - 1] = value as u64;
+                carry = value >> 64;
+            }
+            let value = t[s] as u128 + carry;
+            t[s - 1] = value as u64;
+            t[s] = t[s + 1] + (value >> 64) as u64;
+        }
+        let overflow = t[s] != 0;
+        t.truncate(s);
+        if overflow || compare(&t, &self.n) != Ordering::Less {
+            sub_in(&mut t, &self.n);
+        }
+        t
+    }
+
+    pub fn square(&self, a: &[u64]) -> Vec<u64> {
+        self.mul(a, a)
+    }
+
+    pub fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut sum = a.to_vec();
+        let carry = add_in(&mut sum, b);
+        if carry || compare(&sum, &self.n) != Ordering::Less {
+            sub_in(&mut sum, &self.n);
+        }
+        sum
+    }
+
+    pub fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut difference = a.to_vec();
+        if sub_in(&mut difference, b) {
+            add_in(&mut difference, &self.n);
+        }
+        difference
+    }
+
+    pub fn neg(&self, a: &[u64]) -> Vec<u64> {
+        self.sub(&self.zero(), a)
+    }
+
+    /// `base ^ exponent`, with the exponent as plain little-endian limbs.
+    pub fn pow(&self, base: &[u64], exponent: &[u64]) -> Vec<u64> {
+        let mut result = self.one();
+        for i in (0..64 * exponent.len()).rev() {
+            result = self.square(&result);
+            if exponent[i / 64] >> (i % 64) & 1 == 1 {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+
+    /// The inverse of a nonzero value, by Fermat's little theorem; the modulus must be prime.
+    pub fn invert(&self, a: &[u64]) -> Vec<u64> {
+        let mut exponent = self.n.clone();
+        let mut two = self.zero();
+        two[0] = 2;
+        sub_in(&mut exponent, &two);
+        self.pow(a, &exponent)
+    }
+
+    // value = 2 * value + bit mod n, for a value already below n
+    fn double_in(&self, value: &mut [u64], bit: bool) {
+        let mut carry = bit as u64;
+        for limb in value.iter_mut() {
+            let next = *limb >> 63;
+            *limb = *limb << 1 | carry;
+            carry = next;
+        }
+        if carry == 1 || compare(value, &self.n) != Ordering::Less {
+            sub_in(value, &self.n);
+        }
+    }
+}
+
+/// Reads big-endian bytes into little-endian limbs, at least one.
+pub fn from_be_bytes(bytes: &[u8]) -> Vec<u64> {
+    let mut limbs = vec![0u64; bytes.len().div_ceil(8).max(1)];
+    for (i, byte) in bytes.iter().rev().enumerate() {
+        limbs[i / 8] |= (*byte as u64) << (8 * (i % 8));
+    }
+    limbs
+}
+
+/// Writes little-endian limbs as `len` big-endian bytes, dropping anything above.
+pub fn to_be_bytes(limbs: &[u64], len: usize) -> Vec<u8> {
+    (0..len)
+        .rev()
+        .map(|i| {
+            limbs
+                .get(i / 8)
+                .map_or(0, |limb| (limb >> (8 * (i % 8))) as u8)
+        })
+        .collect()
+}
+
+/// Resizes limbs to `len`, which must not drop any nonzero limb.
+pub fn resize(mut limbs: Vec<u64>, len: usize) -> Vec<u64> {
+    limbs.resize(len, 0);
+    limbs
+}
+
+pub fn is_zero(value: &[u64]) -> bool {
+    value.iter().all(|&limb| limb == 0)
+}
+
+/// Compares equal-length values.
+pub fn compare(a: &[u64], b: &[u64]) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+// a += b, returning the carry out
+fn add_in(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = false;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let (sum, c1) = x.overflowing_add(y);
+        let (sum, c2) = sum.overflowing_add(carry as u64);
+        *x = sum;
+        carry = c1 || c2;
+    }
+    carry
+}
+
+// a -= b, returning the borrow out
+fn sub_in(a: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = false;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let (difference, b1) = x.overflowing_sub(y);
+        let (difference, b2) = difference.overflowing_sub(borrow as u64);
+        *x = difference;
+        borrow = b1 || b2;
+    }
+    borrow
+}
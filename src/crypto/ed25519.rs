@@ -0,0 +1,196 @@
+use super::bignum::{self, Modulus};
+use super::*;
+
+/// 2^255 - 19, big-endian.
+const P: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xed,
+];
+/// The order of the base point, 2^252 + 27742317777372353535851937790883648493, big-endian.
+const L: [u8; 32] = [
+    0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x14, 0xde, 0xf9, 0xde, 0xa2, 0xf7, 0x9c, 0xd6, 0x58, 0x12, 0x63, 0x1a, 0x5c, 0xf5, 0xd3, 0xed,
+];
+/// The encoding of the base point, y = 4/5 with x even.
+const BASE: [u8; 32] = [
+    0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+];
+
+/// Verifies an Ed25519 signature (RFC 8032) of `message` under a 32-byte public key.
+pub fn verify_ed25519(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    if public_key.len() != 32 || signature.len() != 64 {
+        return false;
+    }
+    let curve = Curve::new();
+    let (r, s) = signature.split_at(32);
+    let s = bignum::from_be_bytes(&reversed(s));
+    if bignum::compare(&s, &bignum::from_be_bytes(&L)) != core::cmp::Ordering::Less {
+        return false;
+    }
+    let Some(a) = curve.decode(public_key) else {
+        return false;
+    };
+    let Some(base) = curve.decode(&BASE) else {
+        return false;
+    };
+
+    let mut hashed = r.to_vec();
+    hashed.extend_from_slice(public_key);
+    hashed.extend_from_slice(message);
+    let order = Modulus::from_be_bytes(&L).expect("odd order");
+    let k = order.reduce(&reversed(&sha512(&hashed)));
+
+    // [s]B - [k]A must encode to R
+    let check = curve.add(&curve.mul(&base, &s), &curve.negate(&curve.mul(&a, &k)));
+    curve.encode(&check) == r
+}
+
+/// A point in extended coordinates (X : Y : Z : T), with x = X/Z, y = Y/Z, xy = T/Z.
+#[derive(Clone)]
+struct Point {
+    x: Vec<u64>,
+    y: Vec<u64>,
+    z: Vec<u64>,
+    t: Vec<u64>,
+}
+
+/// The twisted Edwards curve -x^2 + y^2 = 1 + d x^2 y^2, with values in Montgomery form.
+struct Curve {
+    p: Modulus,
+    d: Vec<u64>,
+    d2: Vec<u64>,
+    sqrt_m1: Vec<u64>,
+}
+
+impl Curve {
+    fn new() -> Self {
+        let p = Modulus::from_be_bytes(&P).expect("odd prime");
+        let small = |value: u64| p.to_mont(&bignum::resize(alloc::vec![value], p.limbs()));
+        // d = -121665 / 121666
+        let d = p.mul(&p.neg(&small(121665)), &p.invert(&small(121666)));
+        let d2 = p.add(&d, &d);
+        // sqrt(-1) = 2^((p - 1) / 4)
+        let mut exponent = bignum::from_be_bytes(&P);
+        exponent[0] -= 1;
+        let exponent = shift_right(&exponent, 2);
+        let sqrt_m1 = p.pow(&small(2), &exponent);
+        Curve { p, d, d2, sqrt_m1 }
+    }
+
+    fn identity(&self) -> Point {
+        Point {
+            x: self.p.zero(),
+            y: self.p.one(),
+            z: self.p.one(),
+            t: self.p.zero(),
+        }
+    }
+
+    // RFC 8032, section 5.1.3
+    fn decode(&self, bytes: &[u8]) -> Option<Point> {
+        let p = &self.p;
+        let mut y_bytes = reversed(bytes);
+        let x_sign = y_bytes[0] >> 7;
+        y_bytes[0] &= 0x7f;
+        let y = bignum::from_be_bytes(&y_bytes);
+        if !p.contains(&y) {
+            return None;
+        }
+        let y = p.to_mont(&y);
+        let one = p.one();
+        let yy = p.square(&y);
+        let u = p.sub(&yy, &one);
+        let v = p.add(&p.mul(&self.d, &yy), &one);
+
+        // x = u v^3 (u v^7)^((p - 5) / 8)
+        let v3 = p.mul(&p.square(&v), &v);
+        let v7 = p.mul(&p.square(&v3), &v);
+        let mut exponent = bignum::from_be_bytes(&P);
+        exponent[0] -= 5;
+        let exponent = shift_right(&exponent, 3);
+        let mut x = p.mul(&p.mul(&u, &v3), &p.pow(&p.mul(&u, &v7), &exponent));
+
+        let vxx = p.mul(&v, &p.square(&x));
+        if vxx != u {
+            if vxx != p.neg(&u) {
+                return None;
+            }
+            x = p.mul(&x, &self.sqrt_m1);
+        }
+        let plain = p.to_plain(&x);
+        if bignum::is_zero(&plain) && x_sign == 1 {
+            return None;
+        }
+        if (plain[0] & 1) as u8 != x_sign {
+            x = p.neg(&x);
+        }
+        Some(Point {
+            t: p.mul(&x, &y),
+            x,
+            y,
+            z: one,
+        })
+    }
+
+    fn encode(&self, point: &Point) -> Vec<u8> {
+        let p = &self.p;
+        let z_inverse = p.invert(&point.z);
+        let x = p.to_plain(&p.mul(&point.x, &z_inverse));
+        let y = p.to_plain(&p.mul(&point.y, &z_inverse));
+        let mut bytes = reversed(&bignum::to_be_bytes(&y, 32));
+        bytes[31] |= ((x[0] & 1) as u8) << 7;
+        bytes
+    }
+
+    // add-2008-hwcd-3, complete for this curve, so it doubles too
+    fn add(&self, a: &Point, b: &Point) -> Point {
+        let p = &self.p;
+        let aa = p.mul(&p.sub(&a.y, &a.x), &p.sub(&b.y, &b.x));
+        let bb = p.mul(&p.add(&a.y, &a.x), &p.add(&b.y, &b.x));
+        let cc = p.mul(&p.mul(&a.t, &self.d2), &b.t);
+        let zz = p.mul(&a.z, &b.z);
+        let dd = p.add(&zz, &zz);
+        let e = p.sub(&bb, &aa);
+        let f = p.sub(&dd, &cc);
+        let g = p.add(&dd, &cc);
+        let h = p.add(&bb, &aa);
+        Point {
+            x: p.mul(&e, &f),
+            y: p.mul(&g, &h),
+            z: p.mul(&f, &g),
+            t: p.mul(&e, &h),
+        }
+    }
+
+    fn negate(&self, point: &Point) -> Point {
+        Point {
+            x: self.p.neg(&point.x),
+            y: point.y.clone(),
+            z: point.z.clone(),
+            t: self.p.neg(&point.t),
+        }
+    }
+
+    // Double and add over the plain little-endian limbs of `scalar`
+    fn mul(&self, point: &Point, scalar: &[u64]) -> Point {
+        let mut result = self.identity();
+        for i in (0..64 * scalar.len()).rev() {
+            result = self.add(&result, &result);
+            if scalar[i / 64] >> (i % 64) & 1 == 1 {
+                result = self.add(&result, point);
+            }
+        }
+        result
+    }
+}
+
+fn reversed(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().rev().copied().collect()
+}
+
+fn shift_right(limbs: &[u64], n: u32) -> Vec<u64> {
+    (0..limbs.len())
+        .map(|i| limbs[i] >> n | limbs.get(i + 1).map_or(0, |next| next << (64 - n)))
+        .collect()
+}
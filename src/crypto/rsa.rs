@@ -0,0 +1,82 @@
+use super::bignum::{self, Modulus};
+use super::*;
+
+/// The public exponent Arweave keys use.
+const EXPONENT: u64 = 65537;
+const HASH_LEN: usize = 32;
+
+/// Verifies an RSASSA-PSS signature with SHA-256 and MGF1-SHA-256 under the public key
+/// with big-endian `modulus` and exponent 65537, as Arweave signs. The salt length is
+/// read from the encoded message, so both the 32-byte and empty salts wallets use pass.
+pub fn verify_rsa_pss(modulus: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let Some(n) = Modulus::from_be_bytes(modulus) else {
+        return false;
+    };
+    let k = n.bits().div_ceil(8);
+    let s = bignum::from_be_bytes(signature);
+    if signature.len() != k || s.len() > n.limbs() {
+        return false;
+    }
+    let s = bignum::resize(s, n.limbs());
+    if !n.contains(&s) {
+        return false;
+    }
+
+    let m = n.to_plain(&n.pow(&n.to_mont(&s), &[EXPONENT]));
+    let em_bits = n.bits() - 1;
+    let em_len = em_bits.div_ceil(8);
+    let encoded = bignum::to_be_bytes(&m, k);
+    // When the modulus is a whole number of bytes plus one bit, its top byte is zero
+    if encoded[..k - em_len].iter().any(|&byte| byte != 0) {
+        return false;
+    }
+    emsa_pss_verify(&sha256(message), &encoded[k - em_len..], em_bits)
+}
+
+// EMSA-PSS-VERIFY from RFC 8017, section 9.1.2, recovering the salt length
+fn emsa_pss_verify(m_hash: &[u8; 32], em: &[u8], em_bits: usize) -> bool {
+    let em_len = em.len();
+    if em_len < HASH_LEN + 2 || em[em_len - 1] != 0xbc {
+        return false;
+    }
+    let (masked_db, rest) = em.split_at(em_len - HASH_LEN - 1);
+    let h = &rest[..HASH_LEN];
+    let unused = 8 * em_len - em_bits;
+    let top_mask = (0xffu16 >> unused) as u8;
+    if masked_db[0] & !top_mask != 0 {
+        return false;
+    }
+
+    let mut db = mgf1(h, masked_db.len());
+    for (byte, masked) in db.iter_mut().zip(masked_db) {
+        *byte ^= masked;
+    }
+    db[0] &= top_mask;
+    let Some(separator) = db.iter().position(|&byte| byte != 0) else {
+        return false;
+    };
+    if db[separator] != 0x01 {
+        return false;
+    }
+    let salt = &db[separator + 1..];
+
+    let mut m_prime = Vec::with_capacity(8 + HASH_LEN + salt.len());
+    m_prime.extend_from_slice(&[0; 8]);
+    m_prime.extend_from_slice(m_hash);
+    m_prime.extend_from_slice(salt);
+    sha256(&m_prime) == h
+}
+
+// MGF1 with SHA-256
+fn mgf1(seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len + HASH_LEN);
+    let mut counter = 0u32;
+    while mask.len() < len {
+        let mut block = seed.to_vec();
+        block.extend_from_slice(&counter.to_be_bytes());
+        mask.extend_from_slice(&sha256(&block));
+        counter += 1;
+    }
+    mask.truncate(len);
+    mask
+}
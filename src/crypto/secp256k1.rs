@@ -0,0 +1,178 @@
+use super::bignum::{self, Modulus};
+use super::*;
+
+/// The field prime, 2^256 - 2^32 - 977, big-endian.
+const P: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xfc, 0x2f,
+];
+/// The group order, big-endian.
+const N: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+/// The generator, as an uncompressed point without its 0x04 prefix.
+const G: [u8; 64] = [
+    0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b, 0x07,
+    0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17, 0x98,
+    0x48, 0x3a, 0xda, 0x77, 0x26, 0xa3, 0xc4, 0x65, 0x5d, 0xa4, 0xfb, 0xfc, 0x0e, 0x11, 0x08, 0xa8,
+    0xfd, 0x17, 0xb4, 0x48, 0xa6, 0x85, 0x54, 0x19, 0x9c, 0x47, 0xd0, 0x8f, 0xfb, 0x10, 0xd4, 0xb8,
+];
+
+/// Verifies an ECDSA signature over secp256k1 of a 32-byte message `hash`.
+///
+/// The public key is uncompressed, with or without its 0x04 prefix. The signature is
+/// `r || s`, optionally followed by the recovery byte Ethereum appends, which is ignored.
+pub fn verify_secp256k1(public_key: &[u8], hash: &[u8], signature: &[u8]) -> bool {
+    let public_key = match public_key {
+        [0x04, rest @ ..] if rest.len() == 64 => rest,
+        key if key.len() == 64 => key,
+        _ => return false,
+    };
+    if signature.len() != 64 && signature.len() != 65 {
+        return false;
+    }
+    let curve = Curve::new();
+    let Some(q) = curve.point(public_key) else {
+        return false;
+    };
+    let Some(g) = curve.point(&G) else {
+        return false;
+    };
+
+    let n = &curve.n;
+    let r = bignum::from_be_bytes(&signature[..32]);
+    let s = bignum::from_be_bytes(&signature[32..64]);
+    if bignum::is_zero(&r) || bignum::is_zero(&s) || !n.contains(&r) || !n.contains(&s) {
+        return false;
+    }
+    let z = n.to_mont(&n.reduce(hash));
+    let w = n.invert(&n.to_mont(&s));
+    let u1 = n.to_plain(&n.mul(&z, &w));
+    let u2 = n.to_plain(&n.mul(&n.to_mont(&r), &w));
+
+    let Some(sum) = curve.add(&curve.mul(&g, &u1), &curve.mul(&q, &u2)) else {
+        return false;
+    };
+    // x < p < 2n, so one subtraction reduces it modulo n
+    let x = curve.x(&sum);
+    let x = if n.contains(&x) {
+        x
+    } else {
+        n.sub(&x, &bignum::from_be_bytes(&N))
+    };
+    x == r
+}
+
+/// A finite point in Jacobian coordinates (X : Y : Z), with x = X/Z^2, y = Y/Z^3.
+/// The point at infinity is `None`.
+#[derive(Clone)]
+struct Point {
+    x: Vec<u64>,
+    y: Vec<u64>,
+    z: Vec<u64>,
+}
+
+/// The curve y^2 = x^3 + 7, with coordinates in Montgomery form.
+struct Curve {
+    p: Modulus,
+    n: Modulus,
+}
+
+impl Curve {
+    fn new() -> Self {
+        Curve {
+            p: Modulus::from_be_bytes(&P).expect("odd prime"),
+            n: Modulus::from_be_bytes(&N).expect("odd order"),
+        }
+    }
+
+    // Reads x || y, checking that it lies on the curve
+    fn point(&self, bytes: &[u8]) -> Option<Point> {
+        let p = &self.p;
+        let x = bignum::from_be_bytes(&bytes[..32]);
+        let y = bignum::from_be_bytes(&bytes[32..]);
+        if !p.contains(&x) || !p.contains(&y) {
+            return None;
+        }
+        let (x, y) = (p.to_mont(&x), p.to_mont(&y));
+        let mut seven = p.zero();
+        seven[0] = 7;
+        let rhs = p.add(&p.mul(&p.square(&x), &x), &p.to_mont(&seven));
+        if p.square(&y) != rhs {
+            return None;
+        }
+        Some(Point { x, y, z: p.one() })
+    }
+
+    // The plain affine x coordinate
+    fn x(&self, point: &Point) -> Vec<u64> {
+        let p = &self.p;
+        let z_inverse = p.invert(&point.z);
+        p.to_plain(&p.mul(&point.x, &p.square(&z_inverse)))
+    }
+
+    // dbl-2009-l
+    fn double(&self, a: &Point) -> Option<Point> {
+        let p = &self.p;
+        if bignum::is_zero(&a.y) {
+            return None;
+        }
+        let xx = p.square(&a.x);
+        let yy = p.square(&a.y);
+        let yyyy = p.square(&yy);
+        let s = p.mul(&a.x, &yy);
+        let s = p.add(&s, &s);
+        let s = p.add(&s, &s);
+        let m = p.add(&p.add(&xx, &xx), &xx);
+        let x = p.sub(&p.square(&m), &p.add(&s, &s));
+        let yyyy8 = p.add(&yyyy, &yyyy);
+        let yyyy8 = p.add(&yyyy8, &yyyy8);
+        let yyyy8 = p.add(&yyyy8, &yyyy8);
+        let y = p.sub(&p.mul(&m, &p.sub(&s, &x)), &yyyy8);
+        let z = p.mul(&a.y, &a.z);
+        let z = p.add(&z, &z);
+        Some(Point { x, y, z })
+    }
+
+    // add-1998-cmo-2, falling back to doubling for equal points
+    fn add(&self, a: &Option<Point>, b: &Option<Point>) -> Option<Point> {
+        let p = &self.p;
+        let (a, b) = match (a, b) {
+            (None, b) => return b.clone(),
+            (a, None) => return a.clone(),
+            (Some(a), Some(b)) => (a, b),
+        };
+        let z1z1 = p.square(&a.z);
+        let z2z2 = p.square(&b.z);
+        let u1 = p.mul(&a.x, &z2z2);
+        let u2 = p.mul(&b.x, &z1z1);
+        let s1 = p.mul(&a.y, &p.mul(&b.z, &z2z2));
+        let s2 = p.mul(&b.y, &p.mul(&a.z, &z1z1));
+        if u1 == u2 {
+            return if s1 == s2 { self.double(a) } else { None };
+        }
+        let h = p.sub(&u2, &u1);
+        let r = p.sub(&s2, &s1);
+        let hh = p.square(&h);
+        let hhh = p.mul(&h, &hh);
+        let v = p.mul(&u1, &hh);
+        let x = p.sub(&p.sub(&p.square(&r), &hhh), &p.add(&v, &v));
+        let y = p.sub(&p.mul(&r, &p.sub(&v, &x)), &p.mul(&s1, &hhh));
+        let z = p.mul(&p.mul(&a.z, &b.z), &h);
+        Some(Point { x, y, z })
+    }
+
+    // Double and add over the plain little-endian limbs of `scalar`
+    fn mul(&self, point: &Point, scalar: &[u64]) -> Option<Point> {
+        let mut result = None;
+        let point = Some(point.clone());
+        for i in (0..64 * scalar.len()).rev() {
+            result = result.and_then(|current| self.double(&current));
+            if scalar[i / 64] >> (i % 64) & 1 == 1 {
+                result = self.add(&result, &point);
+            }
+        }
+        result
+    }
+}
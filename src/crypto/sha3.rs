@@ -0,0 +1,118 @@
+use super::*;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+const ROTATIONS: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// Computes SHA3-256 (FIPS 202) of `data`.
+pub fn sha3_256(data: &[u8]) -> Vec<u8> {
+    sponge(data, 136, 0x06, 32)
+}
+
+/// Computes SHA3-512 (FIPS 202) of `data`.
+pub fn sha3_512(data: &[u8]) -> Vec<u8> {
+    sponge(data, 72, 0x06, 64)
+}
+
+/// Computes the original Keccak-256 of `data`, as used by Ethereum.
+pub fn keccak256(data: &[u8]) -> Vec<u8> {
+    sponge(data, 136, 0x01, 32)
+}
+
+/// Absorbs `data` at `rate` bytes per block with the given domain separator,
+/// then squeezes `out_len` bytes.
+fn sponge(data: &[u8], rate: usize, delimiter: u8, out_len: usize) -> Vec<u8> {
+    let mut state = [0u64; 25];
+
+    let mut padded = data.to_vec();
+    padded.push(delimiter);
+    while padded.len() % rate != 0 {
+        padded.push(0);
+    }
+    *padded.last_mut().unwrap() |= 0x80;
+
+    for block in padded.chunks_exact(rate) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks_exact(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(bytes);
+            *lane ^= u64::from_le_bytes(word);
+        }
+        keccak_f(&mut state);
+    }
+
+    let mut out = Vec::with_capacity(out_len);
+    loop {
+        for lane in state.iter().take(rate / 8) {
+            for byte in lane.to_le_bytes() {
+                if out.len() == out_len {
+                    return out;
+                }
+                out.push(byte);
+            }
+        }
+        keccak_f(&mut state);
+    }
+}
+
+/// The Keccak-f[1600] permutation.
+fn keccak_f(a: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS {
+        // Theta
+        let mut c = [0u64; 5];
+        for (x, column) in c.iter_mut().enumerate() {
+            *column = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+
+        // Rho and pi
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                let index = x + 5 * y;
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = a[index].rotate_left(ROTATIONS[index]);
+            }
+        }
+
+        // Chi
+        for x in 0..5 {
+            for y in 0..5 {
+                a[x + 5 * y] = b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+
+        // Iota
+        a[0] ^= rc;
+    }
+}
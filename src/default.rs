@@ -1,4 +1,6 @@
 use super::*;
+use crate::json::encode;
+use crate::state::insert_inbox as insert_inbox_default;
 
 // ANSI color codes matching the assumed Colors table in Lua
 const GRAY: &str = "\x1b[90m";
@@ -7,10 +9,15 @@ const BLUE: &str = "\x1b[34m";
 const RESET: &str = "\x1b[0m";
 
 /// Registers the `default` module with Lua, providing a default message handler.
-/// This handler formats and prints incoming messages, inserting them into the inbox.
-#[mlua::lua_module]
+/// This handler formats and prints incoming messages, inserting them into the inbox
+/// with `insert_inbox`, or `state.insertInbox` when none is given.
+#[cfg_attr(feature = "module", mlua::lua_module)]
 pub fn default(lua: &Lua) -> LuaResult<LuaFunction> {
-    let default_fn = move |lua: &Lua, insert_inbox: LuaFunction| {
+    let default_fn = move |lua: &Lua, insert_inbox: Option<LuaFunction>| {
+        let insert_inbox = match insert_inbox {
+            Some(insert_inbox) => insert_inbox,
+            None => lua.create_function(insert_inbox_default)?,
+        };
         let handler = move |lua: &Lua, msg: LuaTable| {
             // Insert the message into the inbox
             insert_inbox.call::<()>(msg.clone())?;
@@ -41,23 +48,10 @@ pub fn default(lua: &Lua) -> LuaResult<LuaFunction> {
                 // Handle the Data field
                 let data: LuaValue = msg.get("Data")?;
                 let data_str = match data {
-                    LuaValue::Table(t) => {
-                        // Safely attempt to serialize table data using json.encode
-                        let json: LuaResult<LuaTable> = lua.globals().get("json");
-                        match json {
-                            Ok(json_table) => {
-                                let encode: LuaResult<LuaFunction> = json_table.get("encode");
-                                match encode {
-                                    Ok(encode_fn) => match encode_fn.call::<LuaValue>(t) {
-                                        Ok(LuaValue::String(s)) => s.to_str()?.to_string(),
-                                        _ => "<unserializable>".to_string(),
-                                    },
-                                    _ => "<json encode unavailable>".to_string(),
-                                }
-                            }
-                            _ => "<json unavailable>".to_string(),
-                        }
-                    }
+                    LuaValue::Table(t) => match encode(lua, LuaValue::Table(t)) {
+                        Ok(s) => s,
+                        Err(_) => "<unserializable>".to_string(),
+                    },
                     LuaValue::Nil => "".to_string(),
                     _ => data.to_string()?, // Convert other types directly to string
                 };
@@ -0,0 +1,403 @@
+use super::*;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use core::cmp::Ordering;
+
+const VERSION: &str = "0.1.0";
+
+/// Lua's parser gives up at 200 nested C levels, so deeper output could never be loaded.
+const MAX_DEPTH: usize = 180;
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Registers the `dump` module with Lua, exporting `dump` and `load`.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "dump"))]
+pub fn dump_module(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set(
+        "dump",
+        lua.create_function(|_, (value, opts): (LuaValue, Option<LuaTable>)| {
+            dump(value, &Options::from_table(opts)?)
+        })?,
+    )?;
+    exports.set("load", lua.create_function(load)?)?;
+    Ok(exports)
+}
+
+/// Serialization options.
+///
+/// - `sorted`: emit keys in a stable order (booleans, numbers, strings, then tables).
+/// - `compact`: drop all optional whitespace.
+/// - `indent`: spaces per nesting level when not compact.
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub sorted: bool,
+    pub compact: bool,
+    pub indent: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            sorted: true,
+            compact: false,
+            indent: 2,
+        }
+    }
+}
+
+impl Options {
+    fn from_table(opts: Option<LuaTable>) -> LuaResult<Self> {
+        let mut options = Options::default();
+        if let Some(opts) = opts {
+            if let Some(sorted) = opts.get::<Option<bool>>("sorted")? {
+                options.sorted = sorted;
+            }
+            if let Some(compact) = opts.get::<Option<bool>>("compact")? {
+                options.compact = compact;
+            }
+            if let Some(indent) = opts.get::<Option<usize>>("indent")? {
+                options.indent = indent;
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Serializes `value` into a Lua chunk that returns an equivalent value when loaded.
+///
+/// # Behavior
+/// - Booleans, numbers (including `math.huge`, NaN and `math.mininteger`) and strings
+///   round-trip exactly; strings may hold arbitrary bytes.
+/// - Tables reachable more than once, including through cycles, are created up front in
+///   a local `r` and filled in afterwards, so identity is preserved on load.
+/// - Metatables are not serialized; functions, userdata and threads are errors.
+pub fn dump(value: LuaValue, options: &Options) -> LuaResult<String> {
+    let mut dumper = Dumper {
+        options,
+        seen: BTreeMap::new(),
+        order: Vec::new(),
+    };
+    dumper.visit(&value, 0)?;
+
+    let shared: Vec<LuaTable> = dumper
+        .order
+        .iter()
+        .filter(|t| dumper.seen[&pointer(t)].count > 1)
+        .cloned()
+        .collect();
+    for (id, table) in shared.iter().enumerate() {
+        if let Some(entry) = dumper.seen.get_mut(&pointer(table)) {
+            entry.id = Some(id + 1);
+        }
+    }
+
+    let newline = if options.compact { ";" } else { "\n" };
+    let assign = if options.compact { "=" } else { " = " };
+    let mut out = String::new();
+    if !shared.is_empty() {
+        out.push_str(&format!("local r{}{{}}{}", assign, newline));
+        for id in 1..=shared.len() {
+            out.push_str(&format!("r[{}]{}{{}}{}", id, assign, newline));
+        }
+        for (id, table) in shared.iter().enumerate() {
+            for (key, value) in dumper.entries(table)? {
+                out.push_str(&format!("r[{}]", id + 1));
+                match identifier(&key) {
+                    Some(name) => {
+                        out.push('.');
+                        out.push_str(&name);
+                    }
+                    None => {
+                        out.push('[');
+                        dumper.write(&key, 0, &mut out)?;
+                        out.push(']');
+                    }
+                }
+                out.push_str(assign);
+                dumper.write(&value, 0, &mut out)?;
+                out.push_str(newline);
+            }
+        }
+    }
+    out.push_str("return ");
+    dumper.write(&value, 0, &mut out)?;
+    if !options.compact {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Loads a chunk produced by `dump` and returns its value. The chunk runs in an
+/// environment holding only `math`, so it cannot read or change process globals.
+fn load(lua: &Lua, source: LuaString) -> LuaResult<LuaValue> {
+    let env = lua.create_table()?;
+    env.set("math", lua.globals().get::<LuaValue>("math")?)?;
+    lua.load(&*source.as_bytes())
+        .set_name("=dump.load")
+        .set_mode(LuaChunkMode::Text)
+        .set_environment(env)
+        .call(())
+}
+
+struct Seen {
+    count: usize,
+    index: usize,
+    id: Option<usize>,
+}
+
+struct Dumper<'a> {
+    options: &'a Options,
+    seen: BTreeMap<usize, Seen>,
+    order: Vec<LuaTable>,
+}
+
+impl Dumper<'_> {
+    /// Counts references to every reachable table.
+    fn visit(&mut self, value: &LuaValue, depth: usize) -> LuaResult<()> {
+        let table = match value {
+            LuaValue::Table(table) => table,
+            LuaValue::Nil | LuaValue::Boolean(_) | LuaValue::Integer(_) | LuaValue::Number(_) => {
+                return Ok(())
+            }
+            LuaValue::String(_) => return Ok(()),
+            _ => {
+                return Err(LuaError::RuntimeError(format!(
+                    "cannot dump a value of type '{}'",
+                    value.type_name()
+                )))
+            }
+        };
+        if depth > MAX_DEPTH {
+            return Err(LuaError::RuntimeError(
+                "table nesting is too deep to dump".to_string(),
+            ));
+        }
+        let ptr = pointer(table);
+        if let Some(seen) = self.seen.get_mut(&ptr) {
+            seen.count += 1;
+            return Ok(());
+        }
+        self.seen.insert(
+            ptr,
+            Seen {
+                count: 1,
+                index: self.order.len(),
+                id: None,
+            },
+        );
+        self.order.push(table.clone());
+        for pair in table.pairs::<LuaValue, LuaValue>() {
+            let (key, value) = pair?;
+            self.visit(&key, depth + 1)?;
+            self.visit(&value, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the table's entries, in a stable order when `sorted` is set.
+    fn entries(&self, table: &LuaTable) -> LuaResult<Vec<(LuaValue, LuaValue)>> {
+        let mut entries = table
+            .pairs::<LuaValue, LuaValue>()
+            .collect::<LuaResult<Vec<_>>>()?;
+        if self.options.sorted {
+            entries.sort_by(|(a, _), (b, _)| self.compare_keys(a, b));
+        }
+        Ok(entries)
+    }
+
+    fn compare_keys(&self, a: &LuaValue, b: &LuaValue) -> Ordering {
+        fn rank(value: &LuaValue) -> u8 {
+            match value {
+                LuaValue::Boolean(_) => 0,
+                LuaValue::Integer(_) | LuaValue::Number(_) => 1,
+                LuaValue::String(_) => 2,
+                _ => 3,
+            }
+        }
+        match (a, b) {
+            (LuaValue::Boolean(x), LuaValue::Boolean(y)) => x.cmp(y),
+            (LuaValue::Integer(x), LuaValue::Integer(y)) => x.cmp(y),
+            (
+                LuaValue::Integer(_) | LuaValue::Number(_),
+                LuaValue::Integer(_) | LuaValue::Number(_),
+            ) => number(a).partial_cmp(&number(b)).unwrap_or(Ordering::Equal),
+            (LuaValue::String(x), LuaValue::String(y)) => x.as_bytes().cmp(&y.as_bytes()),
+            (LuaValue::Table(x), LuaValue::Table(y)) => {
+                let x = self.seen.get(&pointer(x)).map(|s| s.index);
+                let y = self.seen.get(&pointer(y)).map(|s| s.index);
+                x.cmp(&y)
+            }
+            _ => rank(a).cmp(&rank(b)),
+        }
+    }
+
+    /// Writes a value expression. Shared tables are written as `r[id]`, all other
+    /// tables as inline constructors.
+    fn write(&self, value: &LuaValue, depth: usize, out: &mut String) -> LuaResult<()> {
+        match value {
+            LuaValue::Nil => out.push_str("nil"),
+            LuaValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+            LuaValue::Integer(i) if *i == i64::MIN => out.push_str("math.mininteger"),
+            LuaValue::Integer(i) => out.push_str(&i.to_string()),
+            LuaValue::Number(n) => write_number(*n, out),
+            LuaValue::String(s) => write_string(&s.as_bytes(), out),
+            LuaValue::Table(table) => match self.seen.get(&pointer(table)).and_then(|s| s.id) {
+                Some(id) => out.push_str(&format!("r[{}]", id)),
+                None => self.write_constructor(table, depth, out)?,
+            },
+            _ => {
+                return Err(LuaError::RuntimeError(format!(
+                    "cannot dump a value of type '{}'",
+                    value.type_name()
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn write_constructor(&self, table: &LuaTable, depth: usize, out: &mut String) -> LuaResult<()> {
+        let mut entries = self.entries(table)?;
+        if entries.is_empty() {
+            out.push_str("{}");
+            return Ok(());
+        }
+
+        // Emit the 1..n sequence positionally, ahead of the keyed entries
+        let positions: BTreeMap<i64, usize> = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(position, (key, _))| match key {
+                LuaValue::Integer(i) if *i >= 1 => Some((*i, position)),
+                _ => None,
+            })
+            .collect();
+        let mut in_sequence = vec![false; entries.len()];
+        let mut sequence = Vec::new();
+        let mut next = 1;
+        while let Some(&position) = positions.get(&next) {
+            in_sequence[position] = true;
+            sequence.push(entries[position].1.clone());
+            next += 1;
+        }
+        let mut flags = in_sequence.into_iter();
+        entries.retain(|_| !flags.next().unwrap_or(false));
+
+        let compact = self.options.compact;
+        let inner = " ".repeat(self.options.indent * (depth + 1));
+        let mut items = Vec::new();
+        for value in sequence {
+            let mut item = String::new();
+            self.write(&value, depth + 1, &mut item)?;
+            items.push(item);
+        }
+        for (key, value) in entries {
+            let mut item = match identifier(&key) {
+                Some(name) => name,
+                None => {
+                    let mut item = String::from("[");
+                    self.write(&key, depth + 1, &mut item)?;
+                    item.push(']');
+                    item
+                }
+            };
+            item.push_str(if compact { "=" } else { " = " });
+            self.write(&value, depth + 1, &mut item)?;
+            items.push(item);
+        }
+
+        if compact {
+            out.push('{');
+            out.push_str(&items.join(","));
+            out.push('}');
+        } else {
+            out.push_str("{\n");
+            for item in items {
+                out.push_str(&inner);
+                out.push_str(&item);
+                out.push_str(",\n");
+            }
+            out.push_str(&" ".repeat(self.options.indent * depth));
+            out.push('}');
+        }
+        Ok(())
+    }
+}
+
+fn number(value: &LuaValue) -> f64 {
+    match value {
+        LuaValue::Integer(i) => *i as f64,
+        LuaValue::Number(n) => *n,
+        _ => 0.0,
+    }
+}
+
+fn pointer(table: &LuaTable) -> usize {
+    table.to_pointer() as usize
+}
+
+/// Returns the key as a bare name if it can be written as `name = value`.
+fn identifier(key: &LuaValue) -> Option<String> {
+    let LuaValue::String(s) = key else {
+        return None;
+    };
+    let name = s.to_str().ok()?.to_string();
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name.as_str());
+    valid.then_some(name)
+}
+
+fn write_number(n: f64, out: &mut String) {
+    if n.is_nan() {
+        out.push_str("0/0");
+    } else if n == f64::INFINITY {
+        out.push_str("math.huge");
+    } else if n == f64::NEG_INFINITY {
+        out.push_str("-math.huge");
+    } else {
+        // Debug formatting is the shortest exact representation and keeps a
+        // fraction or exponent, so the value loads back as a float
+        out.push_str(&format!("{:?}", n));
+    }
+}
+
+/// Writes a double-quoted Lua string literal. Valid UTF-8 is kept readable; control
+/// characters and invalid bytes use three-digit decimal escapes.
+fn write_string(bytes: &[u8], out: &mut String) {
+    out.push('"');
+    match core::str::from_utf8(bytes) {
+        Ok(text) => {
+            for c in text.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    c if c.is_ascii_control() => out.push_str(&format!("\\{:03}", c as u32)),
+                    c => out.push(c),
+                }
+            }
+        }
+        Err(_) => {
+            for &byte in bytes {
+                match byte {
+                    b'"' => out.push_str("\\\""),
+                    b'\\' => out.push_str("\\\\"),
+                    b'\n' => out.push_str("\\n"),
+                    0x20..=0x7e => out.push(byte as char),
+                    _ => out.push_str(&format!("\\{:03}", byte)),
+                }
+            }
+        }
+    }
+    out.push('"');
+}
@@ -1,8 +1,31 @@
 use super::*;
+use crate::json::encode;
 use crate::stringify::format as format_fn;
+use crate::utils::tag_value;
+use alloc::collections::BTreeSet;
+use alloc::rc::Rc;
+use core::cell::{Cell, RefCell};
+
+// Where yielded evals wait for their reply, oldest first
+const AWAITING: &str = "ao_rust.eval.awaiting";
+
+/// The most evals that can wait for a reply at once; parking another drops the oldest.
+const MAX_AWAITING: usize = 100;
+
+/// Errors kept in `Errors` until the process sets its own `MAX_ERRORS`.
+const DEFAULT_MAX_ERRORS: i64 = 20;
+
+// Instructions between meter checks, unless the budget is smaller
+const METER_STEP: u64 = 1000;
+
+/// The globals a sandboxed eval can read when `ao.sandbox.globals` is unset.
+const SANDBOX_GLOBALS: [&str; 17] = [
+    "assert", "error", "ipairs", "next", "pairs", "pcall", "print", "select", "tonumber",
+    "tostring", "type", "xpcall", "string", "table", "math", "utf8", "ao",
+];
 
 // Eval module initialization
-#[mlua::lua_module(name = "eval")]
+#[cfg_attr(feature = "module", mlua::lua_module(name = "eval"))]
 pub fn eval_module(lua: &Lua) -> LuaResult<LuaFunction> {
     // Create the outer function that takes `ao` and returns the handler
     lua.create_function(|lua, ao: LuaTable| {
@@ -11,72 +34,386 @@ pub fn eval_module(lua: &Lua) -> LuaResult<LuaFunction> {
             // Extract the expression from msg.Data
             let expr: String = msg.get("Data")?;
 
+            // A `Sandbox = "true"` tag runs the code against a restricted _ENV
+            // Read leniently, so a malformed message still reaches `report`
+            let sandboxed = match tag_value(&msg, "Sandbox")? {
+                Some(LuaValue::String(value)) => *value.as_bytes() == *b"true",
+                _ => false,
+            };
+            let env = if sandboxed {
+                Some(sandbox(lua, &ao)?)
+            } else {
+                None
+            };
+            // Named as aos names its eval chunks, which error messages then show
+            let load = |code: String| {
+                let chunk = lua.load(code).set_name("aos");
+                match &env {
+                    Some(env) => chunk.set_environment(env.clone()),
+                    None => chunk,
+                }
+            };
+
             // Try loading with "return " prefix, fallback to direct expression
-            let func = match lua.load(&format!("return {}", expr)).into_function() {
+            let func = match load(format!("return {}", expr)).into_function() {
                 Ok(f) => f,
-                Err(_) => lua.load(expr).into_function()?,
+                Err(_) => match load(expr).into_function() {
+                    Ok(f) => f,
+                    Err(e) => return report(lua, &ao, msg.get("Id")?, error_table(lua, &e, None)?),
+                },
             };
 
-            // Create a Lua thread (coroutine) to execute the function
-            let thread = lua.create_thread(func)?;
-
-            // Resume the thread with no arguments
-            match thread.resume::<Option<LuaValue>>(()) {
-                Ok(Some(value)) => {
-                    // Execution completed successfully, handle the output
-                    handle_output(lua, &ao, value)?;
-                }
-                Ok(None) => {
-                    // Thread yielded, let AoLoader handle the coroutine state
-                    // No action needed here; the thread state is preserved in memory
-                }
-                Err(e) => {
-                    // Execution failed, set the error
-                    let outbox: LuaTable = ao.get("outbox")?;
-                    let error_msg = match e {
-                        mlua::Error::RuntimeError(msg) => msg,
-                        _ => e.to_string(),
-                    };
-                    // Set outbox.Error
-                    outbox.set("Error", error_msg.clone())?;
-                    // Set global Errors variable to store the last error
-                    lua.globals().set("Errors", error_msg)?;
-                }
-            }
-
-            Ok(())
+            // Create a Lua thread (coroutine) to execute the function, metered by
+            // `ao.limits`; the thread picks up the meter's hook when it is created
+            let mut meter = Meter::start(lua, &ao)?;
+            let capture = PrintCapture::start(lua)?;
+            let status = lua
+                .create_thread(func)
+                .map(|thread| (thread.resume::<LuaMultiValue>(()), thread));
+            let printed = capture.stop(lua)?;
+            let exceeded = meter.stop()?;
+            let (status, thread) = status?;
+            let exceeded = exceeded.or_else(|| meter.memory_error(&status));
+            settle(lua, &ao, msg.get("Id")?, thread, status, exceeded, printed)
         })?;
         Ok(handler)
     })
 }
 
+/// Resumes the eval parked waiting for a message from `msg.From` with its
+/// `X-Reference`, if any, returning whether there was one. The eval's result lands in
+/// the current outbox, as for a fresh eval, and it parks again if it waits once more.
+/// Errors it raises are reported under the Id of the message that started it.
+///
+/// The `_eval` handler parks an eval whose coroutine yields the pattern of a
+/// `Handlers.receive` naming both fields, as `msg.receive()` does, and drops the
+/// one-time handler the receive registered, so only this resumes it. Evals waiting on
+/// other patterns stay with that handler. At most `MAX_AWAITING` evals wait at once.
+pub(crate) fn resume_awaiting(lua: &Lua, ao: &LuaTable, msg: &LuaTable) -> LuaResult<bool> {
+    let Some(awaiting) = lua.named_registry_value::<Option<LuaTable>>(AWAITING)? else {
+        return Ok(false);
+    };
+    let (Some(from), Some(reference)) = (
+        string_field(msg, "From")?,
+        string_field(msg, "X-Reference")?,
+    ) else {
+        return Ok(false);
+    };
+    let Some(index) = find_awaiting(&awaiting, &awaiting_key(&from, &reference))? else {
+        return Ok(false);
+    };
+    let entry: LuaTable = awaiting.raw_get(index)?;
+    awaiting.raw_remove(index)?;
+    let thread: LuaThread = entry.raw_get("thread")?;
+    let message_id: LuaValue = entry.raw_get("messageId")?;
+
+    // Only threads created under limits carry the meter's hook
+    let mut meter = Meter::start(lua, ao)?;
+    let capture = PrintCapture::start(lua)?;
+    let status = thread.resume::<LuaMultiValue>(msg.clone());
+    let printed = capture.stop(lua)?;
+    let exceeded = meter.stop()?;
+    let exceeded = exceeded.or_else(|| meter.memory_error(&status));
+    settle(lua, ao, message_id, thread, status, exceeded, printed)?;
+    Ok(true)
+}
+
+// Records how a run of an eval's coroutine ended, reporting errors under the Id of
+// the message that started the eval
+fn settle(
+    lua: &Lua,
+    ao: &LuaTable,
+    message_id: LuaValue,
+    thread: LuaThread,
+    status: LuaResult<LuaMultiValue>,
+    exceeded: Option<Exceeded>,
+    printed: Vec<String>,
+) -> LuaResult<()> {
+    if let Some(exceeded) = exceeded {
+        return report(lua, ao, message_id, exceeded.to_table(lua)?);
+    }
+
+    match status {
+        Ok(values) if thread.status() == LuaThreadStatus::Resumable => {
+            // Thread yielded, park it if it waits for a reply; the thread state is
+            // preserved in memory. Anything it printed so far is output already.
+            let yielded = values.into_iter().next().unwrap_or(LuaValue::Nil);
+            park(lua, ao, thread, yielded, message_id)?;
+            if !printed.is_empty() {
+                handle_output(lua, ao, LuaMultiValue::new(), printed)?;
+            }
+        }
+        Ok(values) => {
+            // Execution completed successfully (possibly with no values), handle the output
+            handle_output(lua, ao, values, printed)?;
+        }
+        Err(e) => {
+            // Execution failed, report where
+            report(lua, ao, message_id, error_table(lua, &e, Some(&thread))?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Describes an error an eval raised as `{ kind, message, chunk, line, traceback }`.
+///
+/// `kind` is `"syntax"` when the code didn't compile, `"callback"` when a Rust
+/// function it called failed and `"runtime"` otherwise. `chunk` and `line` come from
+/// the position Lua prefixes the message with, when it has one. The traceback is the
+/// failed coroutine's, whose stack Lua leaves in place, or else the one a failed
+/// callback carries.
+fn error_table(lua: &Lua, error: &LuaError, thread: Option<&LuaThread>) -> LuaResult<LuaTable> {
+    let (kind, message, callback_traceback) = match error {
+        LuaError::SyntaxError { message, .. } => ("syntax", message.clone(), None),
+        LuaError::CallbackError { traceback, cause } => {
+            ("callback", cause.to_string(), Some(traceback.clone()))
+        }
+        LuaError::RuntimeError(message) => ("runtime", message.clone(), None),
+        other => ("runtime", other.to_string(), None),
+    };
+
+    let table = lua.create_table()?;
+    table.set("kind", kind)?;
+    if let Some((chunk, line)) = locate(&message) {
+        table.set("chunk", chunk)?;
+        table.set("line", line)?;
+    }
+    table.set("message", message)?;
+    let traceback = match thread {
+        Some(thread) => traceback(lua, thread)?.or(callback_traceback),
+        None => callback_traceback,
+    };
+    table.set("traceback", traceback)?;
+    Ok(table)
+}
+
+// Splits the "chunk:line:" position off a Lua error message
+fn locate(message: &str) -> Option<(&str, u32)> {
+    // Loaded strings are named after their source, which may hold colons itself
+    let end = match message.strip_prefix("[string \"") {
+        Some(rest) => rest.find("\"]:")? + "[string \"\"]".len(),
+        None => message.find(':')?,
+    };
+    let rest = message[end..].strip_prefix(':')?;
+    let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+    let line = rest[..digits].parse().ok()?;
+    rest[digits..]
+        .starts_with(": ")
+        .then_some((&message[..end], line))
+}
+
+// The traceback of a coroutine's stack, if the debug library is loaded
+fn traceback(lua: &Lua, thread: &LuaThread) -> LuaResult<Option<String>> {
+    let Some(debug) = lua.globals().get::<Option<LuaTable>>("debug")? else {
+        return Ok(None);
+    };
+    match debug.get::<Option<LuaFunction>>("traceback")? {
+        Some(traceback) => traceback.call(thread.clone()),
+        None => Ok(None),
+    }
+}
+
+// Sets `outbox.Error` to the error's message, as every other writer sets a string,
+// and appends the whole error to the `Errors` ring, tagged with the Id of the eval's
+// message; the ring drops its oldest entries past `MAX_ERRORS`
+fn report(lua: &Lua, ao: &LuaTable, message_id: LuaValue, error: LuaTable) -> LuaResult<()> {
+    error.set("messageId", message_id)?;
+    let outbox: LuaTable = ao.get("outbox")?;
+    outbox.set("Error", error.get::<LuaValue>("message")?)?;
+    record(lua, error)
+}
+
+// Appends an error to the `Errors` ring
+fn record(lua: &Lua, error: LuaTable) -> LuaResult<()> {
+    let globals = lua.globals();
+    let errors = match globals.get::<LuaValue>("Errors")? {
+        LuaValue::Table(errors) => errors,
+        // Older processes kept only the last error, as a string
+        _ => {
+            let errors = lua.create_table()?;
+            globals.set("Errors", &errors)?;
+            errors
+        }
+    };
+    errors.raw_set(errors.raw_len() + 1, error)?;
+
+    let max = max_errors(lua)?;
+    let len = errors.raw_len() as i64;
+    let overflow = len - max;
+    if overflow > 0 {
+        for i in 1..=len - overflow {
+            errors.raw_set(i, errors.raw_get::<LuaValue>(i + overflow)?)?;
+        }
+        for i in (len - overflow + 1)..=len {
+            errors.raw_set(i, LuaValue::Nil)?;
+        }
+    }
+    Ok(())
+}
+
+// A negative `MAX_ERRORS` keeps none, rather than failing after `outbox.Error` is set
+fn max_errors(lua: &Lua) -> LuaResult<i64> {
+    Ok(lua
+        .globals()
+        .get::<Option<i64>>("MAX_ERRORS")?
+        .map_or(DEFAULT_MAX_ERRORS, |max| max.max(0)))
+}
+
+// Parks a thread that yielded a `From` and `X-Reference` pattern, taking it over
+// from the one-time handler `Handlers.receive` added for that same pattern. An eval
+// already waiting for that reply keeps it, failing this one, and past `MAX_AWAITING`
+// the oldest eval is dropped and recorded in `Errors`.
+fn park(
+    lua: &Lua,
+    ao: &LuaTable,
+    thread: LuaThread,
+    yielded: LuaValue,
+    message_id: LuaValue,
+) -> LuaResult<()> {
+    let LuaValue::Table(pattern) = yielded else {
+        return Ok(());
+    };
+    let (Some(from), Some(reference)) = (
+        string_field(&pattern, "From")?,
+        string_field(&pattern, "X-Reference")?,
+    ) else {
+        return Ok(());
+    };
+
+    if let Some(handlers) = lua.globals().get::<Option<LuaTable>>("Handlers")? {
+        let list: LuaTable = handlers.get("list")?;
+        for i in (1..=list.raw_len()).rev() {
+            let handler: LuaTable = list.raw_get(i)?;
+            if handler.get::<LuaValue>("pattern")? == LuaValue::Table(pattern.clone()) {
+                list.raw_remove(i)?;
+            }
+        }
+    }
+
+    let awaiting = match lua.named_registry_value::<Option<LuaTable>>(AWAITING)? {
+        Some(awaiting) => awaiting,
+        None => {
+            let awaiting = lua.create_table()?;
+            lua.set_named_registry_value(AWAITING, &awaiting)?;
+            awaiting
+        }
+    };
+    let key = awaiting_key(&from, &reference);
+    if find_awaiting(&awaiting, &key)?.is_some() {
+        let message = format!(
+            "an eval is already waiting for the reply from {} with X-Reference {}",
+            from, reference
+        );
+        return report(lua, ao, message_id, dropped_error(lua, message)?);
+    }
+
+    let entry = lua.create_table()?;
+    entry.raw_set("key", key)?;
+    entry.raw_set("thread", thread)?;
+    entry.raw_set("messageId", message_id)?;
+    awaiting.raw_set(awaiting.raw_len() + 1, entry)?;
+    if awaiting.raw_len() > MAX_AWAITING {
+        let oldest: LuaTable = awaiting.raw_get(1)?;
+        awaiting.raw_remove(1)?;
+        let message = format!(
+            "more than {} evals were waiting for replies, dropped the oldest",
+            MAX_AWAITING
+        );
+        // Not this message's failure, so it stays out of `outbox.Error`
+        let error = dropped_error(lua, message)?;
+        error.set("messageId", oldest.raw_get::<LuaValue>("messageId")?)?;
+        record(lua, error)?;
+    }
+    Ok(())
+}
+
+// { kind = "dropped", message = ... } for an eval that will never be resumed
+fn dropped_error(lua: &Lua, message: String) -> LuaResult<LuaTable> {
+    let error = lua.create_table()?;
+    error.set("kind", "dropped")?;
+    error.set("message", message)?;
+    Ok(error)
+}
+
+// The position of the parked eval waiting under `key`
+fn find_awaiting(awaiting: &LuaTable, key: &str) -> LuaResult<Option<usize>> {
+    for (i, entry) in awaiting.sequence_values::<LuaTable>().enumerate() {
+        if entry?.raw_get::<String>("key")? == key {
+            return Ok(Some(i + 1));
+        }
+    }
+    Ok(None)
+}
+
+fn awaiting_key(from: &str, reference: &str) -> String {
+    format!("{}/{}", from, reference)
+}
+
+fn string_field(table: &LuaTable, key: &str) -> LuaResult<Option<String>> {
+    match table.get::<LuaValue>(key)? {
+        LuaValue::String(value) => Ok(Some(value.to_str()?.to_string())),
+        _ => Ok(None),
+    }
+}
+
 // Helper to handle successful output
-fn handle_output(lua: &Lua, ao: &LuaTable, output: LuaValue) -> LuaResult<()> {
+fn handle_output(
+    lua: &Lua,
+    ao: &LuaTable,
+    values: LuaMultiValue,
+    printed: Vec<String>,
+) -> LuaResult<()> {
+    // A lone returned value keeps its type, as the number 2 for `1 + 1`, and several
+    // share a tab-separated line after the printed ones
+    let returned = match values.len() {
+        0 => None,
+        1 => Some(format_value(lua, values[0].clone())?),
+        _ => Some(LuaValue::String(
+            lua.create_string(format_line(lua, values.iter().cloned())?)?,
+        )),
+    };
+
     // Check if HANDLER_PRINT_LOGS is set
     let handler_print_logs: Option<LuaTable> = lua.globals().get("HANDLER_PRINT_LOGS")?;
     if let Some(logs) = handler_print_logs {
-        if !matches!(output, LuaValue::Nil) {
-            let formatted = format_value(lua, output)?;
-            logs.raw_set(logs.raw_len() + 1, formatted)?;
+        for line in printed {
+            logs.raw_set(logs.raw_len() + 1, line)?;
+        }
+        if let Some(returned) = returned.filter(|value| !value.is_nil()) {
+            logs.raw_set(logs.raw_len() + 1, returned)?;
         }
     } else {
         // Set ao.outbox.Output with json, data, and prompt
         let outbox: LuaTable = ao.get("outbox")?;
         let output_table = lua.create_table()?;
 
-        // Set json field
-        let json_value = match output {
-            LuaValue::Table(ref t) => {
-                let formatted: String = format_fn(lua, (t.clone(), None::<i32>, None::<LuaTable>))?;
-                LuaValue::String(lua.create_string(&formatted)?)
-            }
-            _ => LuaValue::String(lua.create_string("undefined")?),
-        };
+        // Set json field, falling back to null for values JSON can't represent
+        // (functions, cycles) so clients can always parse it. Several values encode
+        // as an array, one element per value, so a nil among them stays a null.
+        let json_value = match values.len() {
+            0 => encode(lua, LuaValue::Nil),
+            1 => encode(lua, values[0].clone()),
+            _ => values
+                .iter()
+                .map(|value| encode(lua, value.clone()))
+                .collect::<LuaResult<Vec<_>>>()
+                .map(|items| format!("[{}]", items.join(","))),
+        }
+        .unwrap_or_else(|_| "null".to_string());
         output_table.set("json", json_value)?;
 
         // Set data table
         let data_table = lua.create_table()?;
-        let data_output = format_value(lua, output.clone())?;
+        let data_output = match (printed.is_empty(), returned) {
+            (true, returned) => returned.unwrap_or(LuaValue::Nil),
+            (false, returned) => {
+                let mut lines = printed;
+                if let Some(returned) = returned {
+                    lines.push(format_line(lua, [returned])?);
+                }
+                LuaValue::String(lua.create_string(lines.join("\n"))?)
+            }
+        };
         data_table.set("output", data_output)?;
         data_table.set("prompt", prompt(lua)?)?;
         output_table.set("data", data_table)?;
@@ -89,6 +426,56 @@ fn handle_output(lua: &Lua, ao: &LuaTable, output: LuaValue) -> LuaResult<()> {
     Ok(())
 }
 
+/// Replaces the global `print` while an eval's coroutine runs, collecting the lines it
+/// prints for the eval's output instead.
+struct PrintCapture {
+    original: LuaValue,
+    capture: LuaFunction,
+    lines: Rc<RefCell<Vec<String>>>,
+}
+
+impl PrintCapture {
+    fn start(lua: &Lua) -> LuaResult<Self> {
+        let globals = lua.globals();
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = lines.clone();
+        let capture = lua.create_function(move |lua, args: LuaMultiValue| {
+            let line = format_line(lua, args)?;
+            sink.borrow_mut().push(line);
+            Ok(())
+        })?;
+        let original = globals.get("print")?;
+        globals.set("print", &capture)?;
+        Ok(PrintCapture {
+            original,
+            capture,
+            lines,
+        })
+    }
+
+    /// Puts `print` back, unless the eval replaced it itself, returning the lines.
+    fn stop(self, lua: &Lua) -> LuaResult<Vec<String>> {
+        let globals = lua.globals();
+        if globals.get::<LuaValue>("print")? == LuaValue::Function(self.capture) {
+            globals.set("print", self.original)?;
+        }
+        Ok(self.lines.take())
+    }
+}
+
+// Formats values as print would, but with tables through `stringify`
+fn format_line(lua: &Lua, values: impl IntoIterator<Item = LuaValue>) -> LuaResult<String> {
+    let tostring: LuaFunction = lua.globals().get("tostring")?;
+    let pieces = values
+        .into_iter()
+        .map(|value| match format_value(lua, value)? {
+            LuaValue::String(s) => Ok(s.to_str()?.to_string()),
+            other => tostring.call::<String>(other),
+        })
+        .collect::<LuaResult<Vec<_>>>()?;
+    Ok(pieces.join("\t"))
+}
+
 // Helper to format a value
 fn format_value(lua: &Lua, value: LuaValue) -> LuaResult<LuaValue> {
     match value {
@@ -105,3 +492,291 @@ fn prompt(lua: &Lua) -> LuaResult<LuaValue> {
     let prompt_fn: LuaFunction = lua.globals().get("Prompt")?;
     prompt_fn.call(())
 }
+
+/// A limit an eval ran past: which one, how much it used and what it allowed.
+#[derive(Clone, Copy)]
+struct Exceeded {
+    limit: &'static str,
+    used: u64,
+    max: u64,
+}
+
+impl Exceeded {
+    // { kind = "limit", message = ..., limit = ..., used = ..., max = ... }
+    fn to_table(self, lua: &Lua) -> LuaResult<LuaTable> {
+        let error = lua.create_table()?;
+        error.set("kind", "limit")?;
+        error.set("message", format!("{} limit exceeded", self.limit))?;
+        error.set("limit", self.limit)?;
+        error.set("used", self.used)?;
+        error.set("max", self.max)?;
+        Ok(error)
+    }
+}
+
+/// Meters one eval against `ao.limits`, whose `instructions` field caps the Lua
+/// instructions it runs and whose `memory` field caps the bytes it allocates beyond
+/// what the state held when it started. Unset limits are not enforced.
+///
+/// Instructions are counted by a debug hook every thousand instructions, so `used`
+/// is rounded up to that step. Memory is capped by the allocator where the state owns
+/// it and checked at the same hook otherwise. A hook that finds a limit passed yields
+/// the eval's coroutine, which is then abandoned.
+///
+/// Dropping the meter removes the hook and the cap, so an error between `start` and
+/// `stop` can't leave them on the state.
+struct Meter {
+    lua: Lua,
+    exceeded: Rc<Cell<Option<Exceeded>>>,
+    memory: Option<u64>,
+    baseline: usize,
+    previous_memory_limit: Option<usize>,
+    hooked: bool,
+}
+
+impl Meter {
+    fn start(lua: &Lua, ao: &LuaTable) -> LuaResult<Self> {
+        let limits: Option<LuaTable> = ao.get("limits")?;
+        let (instructions, memory) = match &limits {
+            Some(limits) => (
+                limits.get::<Option<u64>>("instructions")?,
+                limits.get::<Option<u64>>("memory")?,
+            ),
+            None => (None, None),
+        };
+        let baseline = lua.used_memory();
+        let mut meter = Meter {
+            lua: lua.clone(),
+            exceeded: Rc::new(Cell::new(None)),
+            memory,
+            baseline,
+            previous_memory_limit: None,
+            hooked: false,
+        };
+
+        if let Some(memory) = memory {
+            let ceiling = baseline.saturating_add(usize::try_from(memory).unwrap_or(usize::MAX));
+            // Module builds leave allocation to the host, so only the hook checks there
+            meter.previous_memory_limit = lua.set_memory_limit(ceiling).ok();
+        }
+        if instructions.is_some() || memory.is_some() {
+            let step = instructions.map_or(METER_STEP, |max| max.clamp(1, METER_STEP));
+            let exceeded = meter.exceeded.clone();
+            let counted = Cell::new(0u64);
+            lua.set_hook(
+                LuaHookTriggers::new().every_nth_instruction(step as u32),
+                move |lua, _debug| {
+                    let used = counted.get() + step;
+                    counted.set(used);
+                    let allocated = lua.used_memory().saturating_sub(baseline) as u64;
+                    let over = match (instructions, memory) {
+                        (Some(max), _) if used > max => Some(Exceeded {
+                            limit: "instructions",
+                            used,
+                            max,
+                        }),
+                        (_, Some(max)) if allocated > max => Some(Exceeded {
+                            limit: "memory",
+                            used: allocated,
+                            max,
+                        }),
+                        _ => None,
+                    };
+                    // Yield rather than raise, which a pcall in the eval could catch;
+                    // the eval then drops the suspended thread
+                    match exceeded.get().or(over) {
+                        Some(over) => {
+                            exceeded.set(Some(over));
+                            Ok(LuaVmState::Yield)
+                        }
+                        None => Ok(LuaVmState::Continue),
+                    }
+                },
+            );
+            meter.hooked = true;
+        }
+        Ok(meter)
+    }
+
+    /// Removes the hook and the allocation cap, returning the limit the eval ran past.
+    fn stop(&mut self) -> LuaResult<Option<Exceeded>> {
+        self.release()?;
+        Ok(self.exceeded.get())
+    }
+
+    fn release(&mut self) -> LuaResult<()> {
+        if core::mem::take(&mut self.hooked) {
+            self.lua.remove_hook();
+        }
+        if let Some(previous) = self.previous_memory_limit.take() {
+            self.lua.set_memory_limit(previous)?;
+        }
+        Ok(())
+    }
+
+    // An allocation the memory cap refused, which the hook never saw
+    fn memory_error<T>(&self, status: &LuaResult<T>) -> Option<Exceeded> {
+        match (status, self.memory) {
+            (Err(LuaError::MemoryError(_)), Some(max)) => Some(Exceeded {
+                limit: "memory",
+                used: self.lua.used_memory().saturating_sub(self.baseline) as u64,
+                max,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        // Nothing to report a failure to here; `stop` surfaces it on the normal path
+        let _ = self.release();
+    }
+}
+
+/// Builds the `_ENV` of a sandboxed eval from `ao.sandbox`.
+///
+/// Only the names in its `globals` list (`SANDBOX_GLOBALS` by default) read through
+/// to the real globals, with tables behind read-only proxies so `ao.outbox` or
+/// `string.rep` can't be replaced. Names in its `writable` list read and write the
+/// real globals directly. Any other assignment stays in the sandbox and is dropped
+/// with it.
+fn sandbox(lua: &Lua, ao: &LuaTable) -> LuaResult<LuaTable> {
+    let config: Option<LuaTable> = ao.get("sandbox")?;
+    let list = |key: &str| -> LuaResult<Option<BTreeSet<String>>> {
+        match &config {
+            Some(config) => config
+                .get::<Option<LuaTable>>(key)?
+                .map(|names| names.sequence_values().collect())
+                .transpose(),
+            None => Ok(None),
+        }
+    };
+    // Sets, since every global the eval reads is looked up in them
+    let readable = list("globals")?.unwrap_or_else(|| {
+        SANDBOX_GLOBALS
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    });
+    let writable = list("writable")?.unwrap_or_default();
+
+    let globals = lua.globals();
+    // One proxy per table, so `ao == ao` holds inside the sandbox
+    let proxies = lua.create_table()?;
+    let weak = lua.create_table()?;
+    weak.set("__mode", "k")?;
+    proxies.set_metatable(Some(weak));
+
+    let env = lua.create_table()?;
+    env.raw_set("_G", &env)?;
+    let metatable = lua.create_table()?;
+    let real = globals.clone();
+    let write_names = writable.clone();
+    metatable.set(
+        "__index",
+        lua.create_function(move |lua, (_, key): (LuaTable, LuaValue)| {
+            let LuaValue::String(key) = key else {
+                return Ok(LuaValue::Nil);
+            };
+            let name = key.to_str()?;
+            if write_names.contains(&*name) {
+                return real.get::<LuaValue>(&key);
+            }
+            if !readable.contains(&*name) {
+                return Ok(LuaValue::Nil);
+            }
+            read_only(lua, &proxies, real.get(&key)?, &name)
+        })?,
+    )?;
+    metatable.set(
+        "__newindex",
+        lua.create_function(
+            move |_, (env, key, value): (LuaTable, LuaValue, LuaValue)| {
+                let shared = match &key {
+                    LuaValue::String(name) => writable.contains(&*name.to_str()?),
+                    _ => false,
+                };
+                if shared {
+                    globals.set(key, value)
+                } else {
+                    env.raw_set(key, value)
+                }
+            },
+        )?,
+    )?;
+    env.set_metatable(Some(metatable));
+    Ok(env)
+}
+
+// Wraps tables in a proxy that reads through, wrapping nested tables too, and
+// refuses writes; other values pass as they are. The proxy's hidden metatable holds
+// the real table and its path, rather than closures capturing them, which would
+// keep every proxy alive from the registry.
+fn read_only(lua: &Lua, proxies: &LuaTable, value: LuaValue, path: &str) -> LuaResult<LuaValue> {
+    let LuaValue::Table(table) = value else {
+        return Ok(value);
+    };
+    if let Some(proxy) = proxies.raw_get::<Option<LuaTable>>(&table)? {
+        return Ok(LuaValue::Table(proxy));
+    }
+
+    let metatable = lua.create_table()?;
+    metatable.raw_set("target", &table)?;
+    metatable.raw_set("path", path)?;
+    metatable.raw_set("proxies", proxies)?;
+    metatable.raw_set("__index", lua.create_function(proxy_index)?)?;
+    metatable.raw_set("__newindex", lua.create_function(proxy_newindex)?)?;
+    metatable.raw_set("__len", lua.create_function(proxy_len)?)?;
+    metatable.raw_set("__pairs", lua.create_function(proxy_pairs)?)?;
+    metatable.raw_set("__metatable", false)?;
+    let proxy = lua.create_table()?;
+    proxy.set_metatable(Some(metatable));
+
+    proxies.raw_set(&table, &proxy)?;
+    Ok(LuaValue::Table(proxy))
+}
+
+// The real table, its path and the proxy cache behind a proxy
+fn proxied(proxy: &LuaTable) -> LuaResult<(LuaTable, String, LuaTable)> {
+    let metatable = proxy
+        .metatable()
+        .ok_or_else(|| LuaError::RuntimeError("sandbox proxy lost its metatable".to_string()))?;
+    Ok((
+        metatable.raw_get("target")?,
+        metatable.raw_get("path")?,
+        metatable.raw_get("proxies")?,
+    ))
+}
+
+fn proxy_index(lua: &Lua, (proxy, key): (LuaTable, LuaValue)) -> LuaResult<LuaValue> {
+    let (target, path, proxies) = proxied(&proxy)?;
+    let path = match &key {
+        LuaValue::String(key) => format!("{}.{}", path, key.to_str()?),
+        _ => format!("{}[?]", path),
+    };
+    read_only(lua, &proxies, target.get(key)?, &path)
+}
+
+fn proxy_newindex(_lua: &Lua, (proxy, _): (LuaTable, LuaMultiValue)) -> LuaResult<()> {
+    let (_, path, _) = proxied(&proxy)?;
+    Err(LuaError::RuntimeError(format!(
+        "{} is read-only in the sandbox",
+        path
+    )))
+}
+
+fn proxy_len(_lua: &Lua, proxy: LuaTable) -> LuaResult<LuaInteger> {
+    proxied(&proxy)?.0.len()
+}
+
+fn proxy_pairs(lua: &Lua, proxy: LuaTable) -> LuaResult<(LuaFunction, LuaTable, LuaValue)> {
+    Ok((lua.create_function(proxy_next)?, proxy, LuaValue::Nil))
+}
+
+fn proxy_next(lua: &Lua, (proxy, key): (LuaTable, LuaValue)) -> LuaResult<(LuaValue, LuaValue)> {
+    let (target, path, proxies) = proxied(&proxy)?;
+    let next: LuaFunction = lua.globals().get("next")?;
+    let (key, value): (LuaValue, LuaValue) = next.call((target, key))?;
+    Ok((key, read_only(lua, &proxies, value, &path)?))
+}
@@ -1,7 +1,8 @@
 use super::*;
+use crate::message::Message;
 use crate::utils::matches_spec;
 
-#[mlua::lua_module(name = "handlersUtils")]
+#[cfg_attr(feature = "module", mlua::lua_module(name = "handlersUtils"))]
 pub fn handlers_utils(lua: &Lua) -> LuaResult<LuaTable> {
     let exports = lua.create_table()?;
     exports.set("_version", "0.0.2")?;
@@ -19,18 +20,14 @@ pub fn handlers_utils(lua: &Lua) -> LuaResult<LuaTable> {
 fn has_matching_tag(lua: &Lua, (name, value): (LuaString, LuaString)) -> LuaResult<LuaFunction> {
     let name = name.to_str()?.to_string();
     let value = value.to_str()?.to_string();
-    let func = move |_: &Lua, msg: LuaTable| -> LuaResult<LuaValue> {
-        let tags: LuaTable = msg.get("Tags")?;
-        let tag_value: LuaValue = tags.get(name.as_str())?;
-        if let LuaValue::String(s) = tag_value {
-            Ok(LuaValue::Number(if s.to_str()? == value {
+    let func = move |_: &Lua, msg: Message| -> LuaResult<LuaValue> {
+        Ok(LuaValue::Number(
+            if msg.tag(&name) == Some(value.as_str()) {
                 -1.0
             } else {
                 0.0
-            }))
-        } else {
-            Ok(LuaValue::Number(0.0))
-        }
+            },
+        ))
     };
     lua.create_function(func)
 }
@@ -46,18 +43,10 @@ fn has_matching_tag_of(lua: &Lua, (name, values): (LuaString, LuaTable)) -> LuaR
             _ => Err(LuaError::RuntimeError("values must be strings".to_string())),
         })
         .collect::<Result<Vec<_>, _>>()?;
-    let func = move |_lua: &Lua, msg: LuaTable| -> LuaResult<LuaValue> {
-        let tags: LuaTable = msg.get("Tags")?;
-        let tag_value: LuaValue = tags.get(name.as_str())?;
-        if let LuaValue::String(s) = tag_value {
-            let tag_str = s.to_str()?;
-            if values.iter().any(|v| tag_str == v.as_str()) {
-                Ok(LuaValue::Number(-1.0))
-            } else {
-                Ok(LuaValue::Number(0.0))
-            }
-        } else {
-            Ok(LuaValue::Number(0.0))
+    let func = move |_lua: &Lua, msg: Message| -> LuaResult<LuaValue> {
+        match msg.tag(&name) {
+            Some(tag) if values.iter().any(|v| tag == v.as_str()) => Ok(LuaValue::Number(-1.0)),
+            _ => Ok(LuaValue::Number(0.0)),
         }
     };
     lua.create_function(func)
@@ -65,9 +54,8 @@ fn has_matching_tag_of(lua: &Lua, (name, values): (LuaString, LuaTable)) -> LuaR
 
 fn has_matching_data(lua: &Lua, value: LuaString) -> LuaResult<LuaFunction> {
     let value = value.to_str()?.to_string();
-    let func = move |_lua: &Lua, msg: LuaTable| -> LuaResult<LuaValue> {
-        let data: LuaValue = msg.get("Data")?;
-        if let LuaValue::String(s) = data {
+    let func = move |_lua: &Lua, msg: Message| -> LuaResult<LuaValue> {
+        if let LuaValue::String(s) = msg.data {
             Ok(LuaValue::Number(if s.to_str()? == value {
                 -1.0
             } else {
@@ -96,7 +84,8 @@ fn reply(lua: &Lua, input: LuaValue) -> LuaResult<LuaFunction> {
                 ))
             }
         };
-        msg.call_method::<()>("reply", reply_arg)?;
+        // `msg.reply` is a closure over the message, not a method
+        msg.get::<LuaFunction>("reply")?.call::<()>(reply_arg)?;
         Ok(())
     };
     lua.create_function(func)
@@ -109,6 +98,7 @@ fn continue_fn(lua: &Lua, pattern: LuaValue) -> LuaResult<LuaFunction> {
         match match_result {
             LuaValue::Nil => Ok(match_result),
             LuaValue::Boolean(b) if !b => Ok(match_result),
+            LuaValue::Integer(0) => Ok(match_result),
             LuaValue::Number(n) if n == 0.0 => Ok(match_result),
             LuaValue::String(ref s) if s.to_str()? == "skip" => Ok(match_result),
             _ => Ok(LuaValue::Number(1.0)),
@@ -0,0 +1,399 @@
+use super::*;
+use crate::utils::is_array;
+use core::ffi::c_void;
+
+const VERSION: &str = "0.2.0";
+
+// Nesting limit for decoding, keeping hostile input from exhausting the stack
+const MAX_DEPTH: usize = 512;
+
+/// Registers the `json` module with Lua, exporting `encode`, `decode` and the `null` sentinel.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "json"))]
+pub fn json(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set("null", LuaValue::NULL)?;
+    exports.set("encode", lua.create_function(encode)?)?;
+    exports.set("decode", lua.create_function(decode)?)?;
+    Ok(exports)
+}
+
+/// Encodes a Lua value as a JSON string.
+///
+/// # Behavior
+/// - Tables accepted by `utils.isArray` become arrays, tables with string keys become objects.
+/// - Sparse arrays, mixed key types, cycles, non-finite numbers and non-data values
+///   (functions, userdata, threads) are errors.
+/// - `nil` and the `json.null` sentinel encode as `null`.
+/// - Strings that aren't valid UTF-8 are errors, since JSON text can't carry raw bytes.
+pub fn encode(lua: &Lua, value: LuaValue) -> LuaResult<String> {
+    let mut out = String::new();
+    let mut stack = Vec::new();
+    encode_value(lua, &value, &mut stack, &mut out)?;
+    Ok(out)
+}
+
+fn encode_value(
+    lua: &Lua,
+    value: &LuaValue,
+    stack: &mut Vec<*const c_void>,
+    out: &mut String,
+) -> LuaResult<()> {
+    match value {
+        LuaValue::Nil => out.push_str("null"),
+        LuaValue::LightUserData(ud) if ud.0.is_null() => out.push_str("null"),
+        LuaValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        LuaValue::Integer(i) => out.push_str(&i.to_string()),
+        LuaValue::Number(n) => {
+            if !n.is_finite() {
+                return Err(LuaError::RuntimeError(format!(
+                    "unexpected number value '{}'",
+                    n
+                )));
+            }
+            out.push_str(&n.to_string());
+        }
+        LuaValue::String(s) => encode_string(&s.as_bytes(), out)?,
+        LuaValue::Table(t) => {
+            let ptr = t.to_pointer();
+            if stack.contains(&ptr) {
+                return Err(LuaError::RuntimeError("circular reference".to_string()));
+            }
+            stack.push(ptr);
+            encode_table(lua, t, stack, out)?;
+            stack.pop();
+        }
+        _ => {
+            return Err(LuaError::RuntimeError(format!(
+                "unexpected type '{}'",
+                value.type_name()
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn encode_table(
+    lua: &Lua,
+    t: &LuaTable,
+    stack: &mut Vec<*const c_void>,
+    out: &mut String,
+) -> LuaResult<()> {
+    if is_array(lua, LuaValue::Table(t.clone()))? {
+        out.push('[');
+        for (i, value) in t.sequence_values::<LuaValue>().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            encode_value(lua, &value?, stack, out)?;
+        }
+        out.push(']');
+        return Ok(());
+    }
+
+    let mut entries = Vec::new();
+    let mut numeric_keys = true;
+    let mut string_keys = true;
+    for pair in t.pairs::<LuaValue, LuaValue>() {
+        let (k, v) = pair?;
+        match k {
+            LuaValue::String(s) => {
+                numeric_keys = false;
+                entries.push((s, v));
+            }
+            LuaValue::Integer(_) | LuaValue::Number(_) => string_keys = false,
+            _ => {
+                numeric_keys = false;
+                string_keys = false;
+            }
+        }
+    }
+    if !string_keys {
+        return Err(LuaError::RuntimeError(if numeric_keys {
+            "invalid table: sparse array".to_string()
+        } else {
+            "invalid table: mixed or invalid key types".to_string()
+        }));
+    }
+
+    out.push('{');
+    for (i, (k, v)) in entries.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        encode_string(&k.as_bytes(), out)?;
+        out.push(':');
+        encode_value(lua, v, stack, out)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+fn encode_string(bytes: &[u8], out: &mut String) -> LuaResult<()> {
+    let text = core::str::from_utf8(bytes).map_err(|e| {
+        LuaError::RuntimeError(format!(
+            "invalid UTF-8 in string at byte {}",
+            e.valid_up_to() + 1
+        ))
+    })?;
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}
+
+/// Decodes a JSON string into a Lua value.
+///
+/// `null` decodes to `nil` unless a sentinel is given as the second argument
+/// (for example `json.null`), in which case that value is used instead.
+pub fn decode(lua: &Lua, (text, null): (LuaString, Option<LuaValue>)) -> LuaResult<LuaValue> {
+    let bytes = text.as_bytes();
+    let mut decoder = Decoder {
+        lua,
+        bytes: &bytes,
+        pos: 0,
+        null: null.unwrap_or(LuaValue::Nil),
+    };
+    decoder.skip_whitespace();
+    let value = decoder.parse_value(0)?;
+    decoder.skip_whitespace();
+    if decoder.pos < decoder.bytes.len() {
+        return Err(decoder.error("trailing garbage"));
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    lua: &'a Lua,
+    bytes: &'a [u8],
+    pos: usize,
+    null: LuaValue,
+}
+
+impl Decoder<'_> {
+    fn error(&self, msg: &str) -> LuaError {
+        let consumed = &self.bytes[..self.pos.min(self.bytes.len())];
+        let line = consumed.iter().filter(|&&b| b == b'\n').count() + 1;
+        let col = consumed.iter().rev().take_while(|&&b| b != b'\n').count() + 1;
+        LuaError::RuntimeError(format!("{} at line {} col {}", msg, line, col))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str) -> LuaResult<()> {
+        if self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> LuaResult<LuaValue> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("too many nested values"));
+        }
+        match self.peek() {
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'"') => {
+                let s = self.parse_string()?;
+                Ok(LuaValue::String(self.lua.create_string(s)?))
+            }
+            Some(b't') => self.expect_literal("true").map(|_| LuaValue::Boolean(true)),
+            Some(b'f') => self
+                .expect_literal("false")
+                .map(|_| LuaValue::Boolean(false)),
+            Some(b'n') => self.expect_literal("null").map(|_| self.null.clone()),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> LuaResult<LuaValue> {
+        let table = self.lua.create_table()?;
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(LuaValue::Table(table));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected string for key"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':' after key"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let value = self.parse_value(depth + 1)?;
+            table.raw_set(self.lua.create_string(key)?, value)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(LuaValue::Table(table));
+                }
+                _ => return Err(self.error("expected '}' or ','")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> LuaResult<LuaValue> {
+        let table = self.lua.create_table()?;
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(LuaValue::Table(table));
+        }
+        let mut index = 1;
+        loop {
+            self.skip_whitespace();
+            let value = self.parse_value(depth + 1)?;
+            table.raw_set(index, value)?;
+            index += 1;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(LuaValue::Table(table));
+                }
+                _ => return Err(self.error("expected ']' or ','")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> LuaResult<LuaValue> {
+        let start = self.pos;
+        let mut is_float = false;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while let Some(b) = self.peek() {
+            match b {
+                b'0'..=b'9' => {}
+                b'.' | b'e' | b'E' | b'+' | b'-' => is_float = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        let text = core::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or_default();
+        if !is_float {
+            if let Ok(i) = text.parse::<i64>() {
+                return Ok(LuaValue::Integer(i));
+            }
+        }
+        match text.parse::<f64>() {
+            Ok(n) => Ok(LuaValue::Number(n)),
+            Err(_) => Err(self.error(&format!("invalid number '{}'", text))),
+        }
+    }
+
+    fn parse_string(&mut self) -> LuaResult<Vec<u8>> {
+        let mut out = Vec::new();
+        self.pos += 1;
+        loop {
+            let b = match self.peek() {
+                Some(b) => b,
+                None => return Err(self.error("expected closing quote for string")),
+            };
+            self.pos += 1;
+            match b {
+                b'"' => return Ok(out),
+                b'\\' => self.parse_escape(&mut out)?,
+                b if b < 0x20 => return Err(self.error("control character in string")),
+                b => out.push(b),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, out: &mut Vec<u8>) -> LuaResult<()> {
+        let escaped = match self.peek() {
+            Some(b) => b,
+            None => return Err(self.error("expected closing quote for string")),
+        };
+        self.pos += 1;
+        let c = match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.parse_hex4()?;
+                let code = if (0xd800..0xdc00).contains(&high) {
+                    // A high surrogate must be followed by an escaped low surrogate
+                    if !self.bytes[self.pos..].starts_with(b"\\u") {
+                        return Err(self.error("invalid unicode escape in string"));
+                    }
+                    self.pos += 2;
+                    let low = self.parse_hex4()?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(self.error("invalid unicode escape in string"));
+                    }
+                    0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                } else {
+                    high
+                };
+                match char::from_u32(code) {
+                    Some(c) => c,
+                    None => return Err(self.error("invalid unicode escape in string")),
+                }
+            }
+            _ => return Err(self.error("invalid escape char in string")),
+        };
+        let mut buf = [0u8; 4];
+        out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        Ok(())
+    }
+
+    fn parse_hex4(&mut self) -> LuaResult<u32> {
+        let digits = match self.bytes.get(self.pos..self.pos + 4) {
+            Some(digits) => digits,
+            None => return Err(self.error("invalid unicode escape in string")),
+        };
+        let mut code = 0;
+        for &d in digits {
+            let v = match (d as char).to_digit(16) {
+                Some(v) => v,
+                None => return Err(self.error("invalid unicode escape in string")),
+            };
+            code = code * 16 + v;
+        }
+        self.pos += 4;
+        Ok(code)
+    }
+}
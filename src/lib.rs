@@ -8,12 +8,70 @@ use mlua::prelude::*;
 extern crate alloc;
 // Module declarations
 
+pub mod ans104;
 mod ao;
 mod assignment;
+mod base64;
+mod bint;
 mod boot;
+pub mod chance;
+pub mod crypto;
 mod default;
+mod dump;
 mod eval;
+mod handlers;
 mod handlers_utils;
+mod json;
+mod message;
 mod pretty;
+mod process;
+mod state;
 mod stringify;
 mod utils;
+mod weavedrive;
+
+/// Registers every module in `package.preload` under the name aos requires it by
+/// (`.ao`, `.json`, ...), for Lua states that link the crate directly rather than
+/// loading the `luaopen_*` entry points.
+pub fn preload(lua: &Lua) -> LuaResult<()> {
+    let package: LuaTable = lua.globals().get("package")?;
+    let preload: LuaTable = package.get("preload")?;
+    register(lua, &preload, ".ans104", ans104::ans104)?;
+    register(lua, &preload, ".ao", ao::ao)?;
+    register(lua, &preload, ".assignment", assignment::assignment)?;
+    register(lua, &preload, ".base64", base64::base64)?;
+    register(lua, &preload, ".bint", bint::bint)?;
+    register(lua, &preload, ".boot", boot::boot)?;
+    register(lua, &preload, ".chance", chance::chance)?;
+    register(lua, &preload, ".crypto", crypto::crypto)?;
+    register(lua, &preload, ".default", default::default)?;
+    register(lua, &preload, ".dump", dump::dump_module)?;
+    register(lua, &preload, ".eval", eval::eval_module)?;
+    register(lua, &preload, ".handlers", handlers::handlers)?;
+    register(
+        lua,
+        &preload,
+        ".handlers-utils",
+        handlers_utils::handlers_utils,
+    )?;
+    register(lua, &preload, ".json", json::json)?;
+    register(lua, &preload, ".pretty", pretty::pretty)?;
+    register(lua, &preload, ".process", process::process)?;
+    register(lua, &preload, ".state", state::state)?;
+    register(lua, &preload, ".stringify", stringify::stringify)?;
+    register(lua, &preload, ".utils", utils::utils)?;
+    register(lua, &preload, ".weavedrive", weavedrive::weavedrive)?;
+    Ok(())
+}
+
+fn register<R: IntoLua + 'static>(
+    lua: &Lua,
+    preload: &LuaTable,
+    name: &str,
+    open: fn(&Lua) -> LuaResult<R>,
+) -> LuaResult<()> {
+    preload.set(
+        name,
+        lua.create_function(move |lua, _: LuaMultiValue| open(lua))?,
+    )
+}
@@ -0,0 +1,240 @@
+use super::*;
+
+/// A `{ name, value }` message tag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub value: String,
+}
+
+/// A message as handed to the process, read once into typed fields.
+///
+/// `Tags` may be a list of `{ name, value }` tags or a `name = value` map; a map is
+/// sorted by name so both shapes give the same stable list.
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub id: Option<String>,
+    pub target: Option<String>,
+    pub from: Option<String>,
+    pub owner: Option<String>,
+    pub action: Option<String>,
+    pub data: LuaValue,
+    pub tags: Vec<Tag>,
+}
+
+/// The process a message is evaluated in, read from `env.Process`.
+#[derive(Clone, Debug)]
+pub struct Process {
+    pub id: String,
+    pub owner: Option<String>,
+    pub tags: Vec<Tag>,
+}
+
+impl Message {
+    pub fn from_table(lua: &Lua, table: &LuaTable) -> LuaResult<Self> {
+        Ok(Message {
+            id: field(lua, table, "message", "Id")?,
+            target: field(lua, table, "message", "Target")?,
+            from: field(lua, table, "message", "From")?,
+            owner: field(lua, table, "message", "Owner")?,
+            action: field(lua, table, "message", "Action")?,
+            data: table.get("Data")?,
+            tags: tags(lua, table.get("Tags")?, "message")?,
+        })
+    }
+
+    /// The value of the first tag called `name`.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        find_tag(&self.tags, name)
+    }
+}
+
+impl Process {
+    pub fn from_table(lua: &Lua, table: &LuaTable) -> LuaResult<Self> {
+        Ok(Process {
+            id: field(lua, table, "process", "Id")?
+                .ok_or_else(|| malformed("process", "Id is missing"))?,
+            owner: field(lua, table, "process", "Owner")?,
+            tags: tags(lua, table.get("Tags")?, "process")?,
+        })
+    }
+
+    /// The value of the first tag called `name`.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        find_tag(&self.tags, name)
+    }
+
+    /// The values of every tag called `name`, in order.
+    pub fn tag_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.tags
+            .iter()
+            .filter(move |tag| tag.name == name)
+            .map(|tag| tag.value.as_str())
+    }
+}
+
+impl FromLua for Tag {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Table(tag) => Ok(Tag {
+                name: field(lua, &tag, "tag", "name")?
+                    .ok_or_else(|| malformed("tag", "name is missing"))?,
+                value: field(lua, &tag, "tag", "value")?
+                    .ok_or_else(|| malformed("tag", "value is missing"))?,
+            }),
+            other => Err(malformed(
+                "tag",
+                &format!("expected a table, got {}", other.type_name()),
+            )),
+        }
+    }
+}
+
+impl IntoLua for Tag {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let tag = lua.create_table()?;
+        tag.set("name", self.name)?;
+        tag.set("value", self.value)?;
+        Ok(LuaValue::Table(tag))
+    }
+}
+
+impl FromLua for Message {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Table(table) => Message::from_table(lua, &table),
+            other => Err(malformed(
+                "message",
+                &format!("expected a table, got {}", other.type_name()),
+            )),
+        }
+    }
+}
+
+/// Converts back to the wire shape: fields at the root and `Tags` as a list.
+impl IntoLua for Message {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("Id", self.id)?;
+        table.set("Target", self.target)?;
+        table.set("From", self.from)?;
+        table.set("Owner", self.owner)?;
+        table.set("Action", self.action)?;
+        table.set("Data", self.data)?;
+        table.set("Tags", tags_table(lua, self.tags)?)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+impl FromLua for Process {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Table(table) => Process::from_table(lua, &table),
+            other => Err(malformed(
+                "process",
+                &format!("expected a table, got {}", other.type_name()),
+            )),
+        }
+    }
+}
+
+impl IntoLua for Process {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("Id", self.id)?;
+        table.set("Owner", self.owner)?;
+        table.set("Tags", tags_table(lua, self.tags)?)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+/// The error every malformed message, process or tag is reported with.
+pub fn malformed(what: &str, reason: &str) -> LuaError {
+    LuaError::RuntimeError(format!("malformed {}: {}", what, reason))
+}
+
+fn find_tag<'a>(tags: &'a [Tag], name: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|tag| tag.name == name)
+        .map(|tag| tag.value.as_str())
+}
+
+// Reads an optional string field, accepting numbers as Lua would coerce them
+fn field(lua: &Lua, table: &LuaTable, what: &str, key: &str) -> LuaResult<Option<String>> {
+    let value: LuaValue = table.get(key)?;
+    if value.is_nil() {
+        return Ok(None);
+    }
+    let kind = value.type_name();
+    match coerce(lua, value)? {
+        Some(value) => Ok(Some(value)),
+        None => Err(malformed(
+            what,
+            &format!("{} must be a string, got {}", key, kind),
+        )),
+    }
+}
+
+// Strings and numbers as a string, `None` for anything else
+fn coerce(lua: &Lua, value: LuaValue) -> LuaResult<Option<String>> {
+    if !matches!(
+        value,
+        LuaValue::String(_) | LuaValue::Integer(_) | LuaValue::Number(_)
+    ) {
+        return Ok(None);
+    }
+    match lua.coerce_string(value)? {
+        Some(value) => Ok(Some(value.to_str()?.to_string())),
+        None => Ok(None),
+    }
+}
+
+// Reads `Tags` in either shape: a list of `{ name, value }` or a map sorted by name
+fn tags(lua: &Lua, value: LuaValue, what: &str) -> LuaResult<Vec<Tag>> {
+    let table = match value {
+        LuaValue::Nil => return Ok(Vec::new()),
+        LuaValue::Table(table) => table,
+        other => {
+            return Err(malformed(
+                what,
+                &format!("Tags must be a table, got {}", other.type_name()),
+            ))
+        }
+    };
+
+    let len = table.raw_len();
+    if len > 0 {
+        let mut list = Vec::with_capacity(len);
+        for tag in table.sequence_values::<Tag>() {
+            list.push(tag?);
+        }
+        // Anything past the sequence would be silently dropped
+        if table.pairs::<LuaValue, LuaValue>().count() != len {
+            return Err(malformed(what, "Tags mixes a list with named entries"));
+        }
+        return Ok(list);
+    }
+
+    let mut map = Vec::new();
+    for pair in table.pairs::<LuaValue, LuaValue>() {
+        let (name, value) = pair?;
+        let name = match name {
+            LuaValue::String(name) => name.to_str()?.to_string(),
+            other => {
+                return Err(malformed(
+                    what,
+                    &format!("tag names must be strings, got {}", other.type_name()),
+                ))
+            }
+        };
+        let value = coerce(lua, value)?
+            .ok_or_else(|| malformed(what, &format!("tag {} must have a string value", name)))?;
+        map.push(Tag { name, value });
+    }
+    map.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(map)
+}
+
+fn tags_table(lua: &Lua, tags: Vec<Tag>) -> LuaResult<LuaTable> {
+    lua.create_sequence_from(tags)
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 /// Registers the `pretty` module with Lua, exporting `_version` and `tprint`.
-#[mlua::lua_module(name = "pretty")]
+#[cfg_attr(feature = "module", mlua::lua_module(name = "pretty"))]
 pub fn pretty(lua: &Lua) -> LuaResult<LuaTable> {
     let exports = lua.create_table()?;
     exports.set("_version", "0.0.1")?;
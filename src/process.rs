@@ -0,0 +1,151 @@
+use super::*;
+use crate::ao::{enrich, init as ao_init, is_trusted, normalize, result as ao_result, send};
+use crate::default::default as default_module;
+use crate::eval::{eval_module, resume_awaiting};
+use crate::message::Message;
+use crate::state::{check_slice, initialize_state};
+
+const VERSION: &str = "2.0.1";
+
+/// Registers the `process` module with Lua, exporting `handle`, the entry point
+/// that runs a single message through the aos process flow.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "process"))]
+pub fn process(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set("handle", lua.create_function(handle)?)?;
+    Ok(exports)
+}
+
+/// Handles an incoming message and returns the `ao.result` for it.
+///
+/// # Behavior
+/// - Initializes `ao` from the environment, lifts custom tags onto the message and
+///   gives it `reply` and `forward`.
+/// - Rejects assignments that don't match any registered assignable.
+/// - Ignores messages that are neither signed by their sender nor from an authority.
+/// - Hands replies an eval is waiting for back to that eval. Such a reply is consumed:
+///   no handler runs for it.
+/// - Registers the `_eval` and `_default` handlers and runs `Handlers.evaluate` in a
+///   coroutine, kept in `Handlers.coroutines` while a handler waits for a reply.
+/// - Captures handler errors into `ao.outbox.Error`.
+fn handle(lua: &Lua, (msg, env): (LuaTable, LuaTable)) -> LuaResult<LuaTable> {
+    // Hosts pass either the environment itself or a table wrapping it as `env`
+    let env = match env.get::<Option<LuaTable>>("Process")? {
+        Some(_) => env,
+        None => env.get("env")?,
+    };
+
+    ensure_modules(lua)?;
+    ao_init(lua, env.clone())?;
+    let msg = enrich(lua, normalize(lua, msg)?)?;
+    initialize_state(lua, &msg, &env)?;
+
+    let ao: LuaTable = lua.globals().get("ao")?;
+    if ao.get::<Option<LuaFunction>>("isAssignment")?.is_none() {
+        crate::assignment::init(lua, ao.clone())?;
+    }
+
+    // Only accept assignments that match one of the process assignables
+    let is_assignment: bool = ao.get::<LuaFunction>("isAssignment")?.call(msg.clone())?;
+    let is_assignable: bool = ao.get::<LuaFunction>("isAssignable")?.call(msg.clone())?;
+    if is_assignment && !is_assignable {
+        let res = lua.create_table()?;
+        res.set("Error", "Assignment is not trusted by this process!")?;
+        return ao_result(lua, res);
+    }
+
+    // Only trust messages from a signed owner or an authority
+    let message = Message::from_table(lua, &msg)?;
+    let from = message.from.unwrap_or_default();
+    let owner = message.owner.unwrap_or_default();
+    if from != owner && !is_trusted(lua, msg.clone())? {
+        let ao_id: String = ao.get("id")?;
+        if from != ao_id {
+            let reply = lua.create_table()?;
+            reply.set("Target", from.clone())?;
+            reply.set("Data", "Message is not trusted by this process!")?;
+            send(lua, reply)?;
+        }
+        let print: LuaFunction = lua.globals().get("print")?;
+        print.call::<()>(format!(
+            "Message is not trusted! From: {} - Owner: {}",
+            from, owner
+        ))?;
+        return ao_result(lua, lua.create_table()?);
+    }
+
+    // A reply to a parked eval goes to that eval rather than the handlers
+    if resume_awaiting(lua, &ao, &msg)? {
+        return ao_result(lua, lua.create_table()?);
+    }
+
+    register_handlers(lua, &ao)?;
+
+    // Evaluate in a coroutine so handlers can yield waiting for replies
+    let handlers: LuaTable = lua.globals().get("Handlers")?;
+    let evaluate: LuaFunction = handlers.get("evaluate")?;
+    let thread = lua.create_thread(evaluate)?;
+    let status = thread.resume::<LuaValue>((msg.clone(), env));
+
+    // Keep a reference to the coroutine if it will wake up, and drop finished ones
+    let coroutines: LuaTable = handlers.get("coroutines")?;
+    if thread.status() == LuaThreadStatus::Resumable {
+        coroutines.push(thread)?;
+    }
+    for i in (1..=coroutines.raw_len()).rev() {
+        let co: LuaThread = coroutines.get(i)?;
+        if co.status() != LuaThreadStatus::Resumable {
+            coroutines.raw_remove(i)?;
+        }
+    }
+
+    if let Err(e) = status {
+        let error_msg = match e {
+            mlua::Error::RuntimeError(msg) => msg,
+            _ => e.to_string(),
+        };
+        let action: Option<String> = msg.get("Action")?;
+        let print: LuaFunction = lua.globals().get("print")?;
+        print.call::<()>(format!(
+            "Error handling message with Action = {}",
+            action.as_deref().unwrap_or("No Action")
+        ))?;
+        print.call::<()>(error_msg.clone())?;
+        let outbox: LuaTable = ao.get("outbox")?;
+        outbox.set("Error", error_msg)?;
+    }
+
+    ao_result(lua, lua.create_table()?)
+}
+
+/// Loads the native `ao` and `Handlers` modules into globals when the host hasn't.
+fn ensure_modules(lua: &Lua) -> LuaResult<()> {
+    let globals = lua.globals();
+    if globals.get::<Option<LuaTable>>("ao")?.is_none() {
+        globals.set("ao", crate::ao::ao(lua)?)?;
+    }
+    if globals.get::<Option<LuaTable>>("Handlers")?.is_none() {
+        globals.set("Handlers", crate::handlers::handlers(lua)?)?;
+    }
+    Ok(())
+}
+
+/// Registers the built-in `_eval` and `_default` handlers.
+fn register_handlers(lua: &Lua, ao: &LuaTable) -> LuaResult<()> {
+    let handlers: LuaTable = lua.globals().get("Handlers")?;
+
+    let is_eval =
+        lua.create_function(|lua, msg: LuaTable| Ok(check_slice(lua, &msg)? == "eval"))?;
+    let eval_handler: LuaFunction = eval_module(lua)?.call(ao.clone())?;
+    handlers
+        .get::<LuaFunction>("add")?
+        .call::<()>(("_eval", is_eval, eval_handler))?;
+
+    let always = lua.create_function(|_, _: LuaMultiValue| Ok(true))?;
+    let default_handler: LuaFunction = default_module(lua)?.call(())?;
+    handlers
+        .get::<LuaFunction>("append")?
+        .call::<()>(("_default", always, default_handler))?;
+    Ok(())
+}
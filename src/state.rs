@@ -0,0 +1,120 @@
+use super::*;
+
+const VERSION: &str = "0.1.0";
+
+/// Inbox bound used until the process sets its own `MAX_INBOX_SIZE`.
+pub const DEFAULT_MAX_INBOX_SIZE: i64 = 10000;
+
+/// Registers the `state` module with Lua, which owns the process globals: `Inbox`,
+/// `MAX_INBOX_SIZE`, `Owner`, `Name` and the `Initialized` and `Seeded` flags.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "state"))]
+pub fn state(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set("insertInbox", lua.create_function(insert_inbox)?)?;
+    exports.set(
+        "initializeState",
+        lua.create_function(|lua, (msg, env): (LuaTable, LuaTable)| {
+            initialize_state(lua, &msg, &env)
+        })?,
+    )?;
+    exports.set("reset", lua.create_function(|lua, ()| reset(lua))?)?;
+    exports.set(
+        "checkSlice",
+        lua.create_function(|lua, msg: LuaTable| check_slice(lua, &msg))?,
+    )?;
+    Ok(exports)
+}
+
+/// Appends a message to `Inbox`, evicting the oldest messages once it holds more
+/// than `MAX_INBOX_SIZE`.
+pub(crate) fn insert_inbox(lua: &Lua, msg: LuaTable) -> LuaResult<()> {
+    let globals = lua.globals();
+    let inbox = match globals.get::<Option<LuaTable>>("Inbox")? {
+        Some(inbox) => inbox,
+        None => {
+            let inbox = lua.create_table()?;
+            globals.set("Inbox", inbox.clone())?;
+            inbox
+        }
+    };
+    inbox.push(msg)?;
+
+    let max = max_inbox_size(lua)?;
+    let overflow = inbox.raw_len() as i64 - max;
+    if overflow > 0 {
+        // Shift the survivors down in one pass instead of one `table.remove` per message
+        let len = inbox.raw_len() as i64;
+        for i in 1..=len - overflow {
+            inbox.raw_set(i, inbox.raw_get::<LuaValue>(i + overflow)?)?;
+        }
+        for i in (len - overflow + 1)..=len {
+            inbox.raw_set(i, LuaValue::Nil)?;
+        }
+    }
+    Ok(())
+}
+
+fn max_inbox_size(lua: &Lua) -> LuaResult<i64> {
+    match lua.globals().get::<Option<i64>>("MAX_INBOX_SIZE")? {
+        Some(max) if max >= 0 => Ok(max),
+        Some(_) => Err(LuaError::RuntimeError(
+            "MAX_INBOX_SIZE must not be negative".to_string(),
+        )),
+        None => Ok(DEFAULT_MAX_INBOX_SIZE),
+    }
+}
+
+/// Creates the process globals on first use, records the process owner, and seeds
+/// `chance` from the first message the process handles.
+pub(crate) fn initialize_state(lua: &Lua, msg: &LuaTable, env: &LuaTable) -> LuaResult<()> {
+    let globals = lua.globals();
+    if globals.get::<Option<LuaTable>>("Inbox")?.is_none() {
+        globals.set("Inbox", lua.create_table()?)?;
+    }
+    if globals.get::<Option<LuaValue>>("MAX_INBOX_SIZE")?.is_none() {
+        globals.set("MAX_INBOX_SIZE", DEFAULT_MAX_INBOX_SIZE)?;
+    }
+    if globals.get::<Option<String>>("Name")?.is_none() {
+        globals.set("Name", "aos")?;
+    }
+    let owner: Option<String> = globals.get("Owner")?;
+    if owner.as_deref().unwrap_or("").is_empty() {
+        let process: LuaTable = env.get("Process")?;
+        let process_owner: Option<String> = process.get("Owner")?;
+        globals.set("Owner", process_owner.unwrap_or_default())?;
+    }
+    if globals.get::<Option<LuaFunction>>("Prompt")?.is_none() {
+        globals.set("Prompt", lua.create_function(|_, ()| Ok("aos> "))?)?;
+    }
+    if !globals.get::<Option<bool>>("Seeded")?.unwrap_or(false) {
+        crate::chance::seed_from_message(lua, msg)?;
+        globals.set("Seeded", true)?;
+    }
+    globals.set("Initialized", true)?;
+    Ok(())
+}
+
+/// Empties `Inbox`, returning the number of messages dropped.
+pub(crate) fn reset(lua: &Lua) -> LuaResult<usize> {
+    let globals = lua.globals();
+    let dropped = match globals.get::<Option<LuaTable>>("Inbox")? {
+        Some(inbox) => inbox.raw_len(),
+        None => 0,
+    };
+    globals.set("Inbox", lua.create_table()?)?;
+    Ok(dropped)
+}
+
+/// Tells whether a message is an `Eval` from the owner, which is run as code, or an
+/// ordinary message for the handlers. Returns `"eval"` or `"message"`.
+pub(crate) fn check_slice(lua: &Lua, msg: &LuaTable) -> LuaResult<&'static str> {
+    let action: Option<String> = msg.get("Action")?;
+    let from: Option<String> = msg.get("From")?;
+    let owner: Option<String> = lua.globals().get("Owner")?;
+    if action.as_deref() == Some("Eval") && from.is_some() && from == owner {
+        Ok("eval")
+    } else {
+        Ok("message")
+    }
+}
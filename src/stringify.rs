@@ -21,7 +21,7 @@ const COLORS: Colors = Colors {
 };
 
 // Stringify module initialization
-#[mlua::lua_module(name = "stringify")]
+#[cfg_attr(feature = "module", mlua::lua_module(name = "stringify"))]
 pub fn stringify(lua: &Lua) -> LuaResult<LuaTable> {
     let exports = lua.create_table()?;
     exports.set("_version", VERSION)?;
@@ -37,7 +37,10 @@ fn is_simple_array(_lua: &Lua, tbl: LuaTable) -> LuaResult<bool> {
         let (key, value) = pair?;
         match key {
             LuaValue::Integer(k) if k == array_index => {
-                if !matches!(value, LuaValue::Number(_) | LuaValue::String(_)) {
+                if !matches!(
+                    value,
+                    LuaValue::Integer(_) | LuaValue::Number(_) | LuaValue::String(_)
+                ) {
                     return Ok(false);
                 }
                 array_index += 1;
@@ -48,6 +51,11 @@ fn is_simple_array(_lua: &Lua, tbl: LuaTable) -> LuaResult<bool> {
     Ok(true)
 }
 
+// Render a value with Lua's `tostring`, which keeps the `.0` of integral floats
+fn tostring(lua: &Lua, value: LuaValue) -> LuaResult<String> {
+    lua.globals().get::<LuaFunction>("tostring")?.call(value)
+}
+
 // Format a table for display
 pub fn format(
     lua: &Lua,
@@ -67,9 +75,9 @@ pub fn format(
             let v = value?;
             let formatted = match v {
                 LuaValue::String(s) => {
-                    format!("{}{}{}{}", COLORS.green, '"', s.to_str()?, COLORS.reset)
+                    format!("{}\"{}\"{}", COLORS.green, s.to_str()?, COLORS.reset)
                 }
-                _ => format!("{}{}{}", COLORS.blue, v.to_string()?, COLORS.reset),
+                _ => format!("{}{}{}", COLORS.blue, tostring(lua, v)?, COLORS.reset),
             };
             result.push(formatted);
         }
@@ -89,9 +97,9 @@ pub fn format(
                             format(lua, (t.clone(), Some(indent + 2), Some(visited.clone())))?
                         }
                         LuaValue::String(ref s) => {
-                            format!("{}{}{}{}", COLORS.green, '"', s.to_str()?, COLORS.reset)
+                            format!("{}\"{}\"{}", COLORS.green, s.to_str()?, COLORS.reset)
                         }
-                        _ => format!("{}{}{}", COLORS.blue, v.to_string()?, COLORS.reset),
+                        _ => format!("{}{}{}", COLORS.blue, tostring(lua, v)?, COLORS.reset),
                     };
                     result.push(format!("{}{}", to_indent_child, formatted));
                 } else {
@@ -115,9 +123,9 @@ pub fn format(
                     }
                 }
                 LuaValue::String(ref s) => {
-                    format!("{}{}{}{}", COLORS.green, '"', s.to_str()?, COLORS.reset)
+                    format!("{}\"{}\"{}", COLORS.green, s.to_str()?, COLORS.reset)
                 }
-                _ => format!("{}{}{}", COLORS.blue, v.to_string()?, COLORS.reset),
+                _ => format!("{}{}{}", COLORS.blue, tostring(lua, v)?, COLORS.reset),
             };
             result.push(format!(
                 "{}{} = {}",
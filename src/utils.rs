@@ -2,7 +2,7 @@ use super::*;
 
 const VERSION: &str = "0.0.1";
 
-#[mlua::lua_module(name = "utils")]
+#[cfg_attr(feature = "module", mlua::lua_module(name = "utils"))]
 pub fn utils(lua: &Lua) -> LuaResult<LuaTable> {
     let exports = lua.create_table()?;
 
@@ -36,11 +36,12 @@ pub fn matches_spec(lua: &Lua, (msg, spec): (LuaTable, LuaValue)) -> LuaResult<L
         LuaValue::Table(table) => {
             for pair in table.pairs::<LuaString, LuaValue>() {
                 let (key, pattern) = pair?;
-                let key_str_owned = key.to_str()?; // Get the owned string
-                let key_str = key_str_owned.as_ref(); // Convert to &str
-                let msg_value: Option<LuaValue> = msg.get(key_str)?;
-                let tags: Option<LuaTable> = msg.get("Tags")?;
-                let tag_value: Option<LuaValue> = tags.and_then(|t| t.get(key_str).ok());
+                let key_str = key.to_str()?;
+                // A missing or false value counts as absent, as in `not msg[key]`
+                let msg_value: Option<LuaValue> = msg
+                    .get::<Option<LuaValue>>(key_str.as_ref())?
+                    .filter(is_present);
+                let tag_value = tag_value(&msg, key_str.as_ref())?;
                 if msg_value.is_none() && tag_value.is_none() {
                     return Ok(LuaValue::Boolean(false));
                 }
@@ -57,15 +58,43 @@ pub fn matches_spec(lua: &Lua, (msg, spec): (LuaTable, LuaValue)) -> LuaResult<L
             Ok(LuaValue::Boolean(true))
         }
         LuaValue::String(s) => {
-            let action: Option<String> = msg.get("Action")?;
-            let s_str = s.to_str()?;
-            let matches = action.map_or(false, |a| a.as_str() == s_str.as_ref());
+            let matches = match msg.get::<LuaValue>("Action")? {
+                LuaValue::String(action) => *action.as_bytes() == *s.as_bytes(),
+                _ => false,
+            };
             Ok(LuaValue::Boolean(matches))
         }
         _ => Ok(LuaValue::Boolean(false)),
     }
 }
 
+fn is_present(value: &LuaValue) -> bool {
+    !matches!(value, LuaValue::Nil | LuaValue::Boolean(false))
+}
+
+// The value of tag `key`, from either a tag list or a `name = value` map. Malformed
+// entries are skipped, so a bad tag makes the pattern miss rather than fail dispatch.
+pub(crate) fn tag_value(msg: &LuaTable, key: &str) -> LuaResult<Option<LuaValue>> {
+    let tags = match msg.get::<LuaValue>("Tags")? {
+        LuaValue::Table(tags) => tags,
+        _ => return Ok(None),
+    };
+    if tags.raw_len() == 0 {
+        return Ok(tags.raw_get::<Option<LuaValue>>(key)?.filter(is_present));
+    }
+    for tag in tags.sequence_values::<LuaValue>() {
+        let LuaValue::Table(tag) = tag? else { continue };
+        let name = match tag.raw_get::<LuaValue>("name")? {
+            LuaValue::String(name) => name,
+            _ => continue,
+        };
+        if *name.as_bytes() == *key.as_bytes() {
+            return Ok(tag.raw_get::<Option<LuaValue>>("value")?.filter(is_present));
+        }
+    }
+    Ok(None)
+}
+
 // fn matches_pattern(lua: &Lua, (pattern, value, msg): (LuaValue, LuaValue, LuaTable)) -> LuaResult<bool> {
 //     matches_pattern_helper(lua, pattern, value, msg)
 // }
@@ -144,15 +173,12 @@ pub fn is_array(_lua: &Lua, table: LuaValue) -> LuaResult<bool> {
         let mut max_index: i64 = 0;
         for pair in tbl.pairs::<LuaValue, LuaValue>() {
             let (k, _) = pair?;
-            if let LuaValue::Number(n) = k {
-                let int_n = n.floor();
-                if n < 1.0 || n != int_n {
-                    return Ok(false); // Non-integer or negative key
-                }
-                max_index = max_index.max(int_n as i64);
-            } else {
-                return Ok(false); // Non-numeric key
-            }
+            let index = match k {
+                LuaValue::Integer(i) if i >= 1 => i,
+                LuaValue::Number(n) if n >= 1.0 && n == n.floor() => n as i64,
+                _ => return Ok(false), // Non-numeric, non-integer or negative key
+            };
+            max_index = max_index.max(index);
         }
         // Compare max_index with table length
         let len = tbl.len()?.try_into().unwrap_or(0);
@@ -206,7 +232,7 @@ fn concat(lua: &Lua, a: LuaTable) -> LuaResult<LuaFunction> {
 
     let concat_inner = move |lua: &Lua, b: LuaTable| {
         // Verify that b is an array
-        if !is_array(lua, LuaValue::Table(a.clone()))? {
+        if !is_array(lua, LuaValue::Table(b.clone()))? {
             return Err(LuaError::RuntimeError(
                 "second argument should be a table that is an array".to_string(),
             ));
@@ -0,0 +1,131 @@
+use super::*;
+use crate::json::decode;
+
+const VERSION: &str = "0.1.0";
+
+/// Registers the `weavedrive` module with Lua. WeaveDrive exposes Arweave through
+/// virtual files: `/data/<id>` holds transaction data, `/tx/<id>` transaction and
+/// data item headers, and `/block/<height>` block headers. Headers are JSON and are
+/// returned decoded.
+///
+/// Lookups return `nil, message` when the file doesn't exist, like `io.open`.
+#[cfg_attr(feature = "module", mlua::lua_module(name = "weavedrive"))]
+pub fn weavedrive(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set("_version", VERSION)?;
+    exports.set(
+        "getData",
+        lua.create_function(|lua, tx_id: String| {
+            require_attestation(lua)?;
+            Ok(found(get_data(lua, &tx_id)?.map(LuaValue::String), "Data"))
+        })?,
+    )?;
+    exports.set(
+        "getTx",
+        lua.create_function(|lua, tx_id: String| {
+            require_attestation(lua)?;
+            Ok(found(
+                read_json(lua, &format!("/tx/{}", tx_id))?,
+                "Transaction",
+            ))
+        })?,
+    )?;
+    exports.set(
+        "getBlock",
+        lua.create_function(|lua, height: LuaValue| {
+            require_attestation(lua)?;
+            let height = match height {
+                LuaValue::Integer(i) if i >= 0 => i.to_string(),
+                LuaValue::String(s) => s.to_str()?.to_string(),
+                _ => {
+                    return Err(LuaError::RuntimeError(
+                        "block height must be a non-negative integer".to_string(),
+                    ))
+                }
+            };
+            Ok(found(
+                read_json(lua, &format!("/block/{}", height))?,
+                "Block header",
+            ))
+        })?,
+    )?;
+    exports.set(
+        "getDataItem",
+        lua.create_function(|lua, id: String| {
+            require_attestation(lua)?;
+            Ok(found(read_json(lua, &format!("/tx/{}", id))?, "Data item"))
+        })?,
+    )?;
+    Ok(exports)
+}
+
+/// Reads `/data/<tx_id>`, returning `None` if the file doesn't exist.
+pub(crate) fn get_data(lua: &Lua, tx_id: &str) -> LuaResult<Option<LuaString>> {
+    read_file(lua, &format!("/data/{}", tx_id))
+}
+
+/// Reads a whole file through Lua's io library, where the host mounts WeaveDrive.
+fn read_file(lua: &Lua, path: &str) -> LuaResult<Option<LuaString>> {
+    let io: LuaTable = lua.globals().get("io")?;
+    let open: LuaFunction = io.get("open")?;
+    let file_result: LuaValue = open.call((path, "r"))?;
+    match file_result {
+        LuaValue::UserData(file) => {
+            let contents: LuaString = file.call_method("read", "*a")?;
+            file.call_method::<()>("close", ())?;
+            Ok(Some(contents))
+        }
+        LuaValue::Nil => Ok(None),
+        _ => Err(LuaError::RuntimeError(
+            "io.open returned unexpected value".to_string(),
+        )),
+    }
+}
+
+fn read_json(lua: &Lua, path: &str) -> LuaResult<Option<LuaValue>> {
+    match read_file(lua, path)? {
+        Some(contents) => Ok(Some(decode(lua, (contents, None))?)),
+        None => Ok(None),
+    }
+}
+
+fn found(value: Option<LuaValue>, what: &str) -> (LuaValue, Option<String>) {
+    match value {
+        Some(value) => (value, None),
+        None => (LuaValue::Nil, Some(format!("{} not found!", what))),
+    }
+}
+
+/// Hosts only mount WeaveDrive for processes spawned with an `Extension: WeaveDrive`
+/// tag, so fail with an explanation rather than a bare missing file.
+fn require_attestation(lua: &Lua) -> LuaResult<()> {
+    let tags = lua
+        .globals()
+        .get::<Option<LuaTable>>("ao")?
+        .map(|ao| ao.get::<Option<LuaTable>>("env"))
+        .transpose()?
+        .flatten()
+        .map(|env| env.get::<Option<LuaTable>>("Process"))
+        .transpose()?
+        .flatten()
+        .map(|process| process.get::<Option<LuaTable>>("Tags"))
+        .transpose()?
+        .flatten();
+    let Some(tags) = tags else {
+        return Err(LuaError::RuntimeError(
+            "WeaveDrive requires ao.env.Process, which is not initialized".to_string(),
+        ));
+    };
+    for tag in tags.sequence_values::<LuaTable>() {
+        let tag = tag?;
+        let name: Option<String> = tag.get("name")?;
+        let value: Option<String> = tag.get("value")?;
+        if name.as_deref() == Some("Extension") && value.as_deref() == Some("WeaveDrive") {
+            return Ok(());
+        }
+    }
+    Err(LuaError::RuntimeError(
+        "WeaveDrive is not enabled for this process; spawn it with the tag Extension: WeaveDrive"
+            .to_string(),
+    ))
+}
@@ -0,0 +1,364 @@
+mod common;
+
+use ao_rust::ans104::{
+    decode_tags, deep_hash, encode_tags, DataItem, DataItemError, DeepHashChunk, SignatureType, Tag,
+};
+use ao_rust::crypto::verify_ed25519;
+use common::{lua, render};
+
+fn tag(name: &str, value: &str) -> Tag {
+    Tag {
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn tags_are_avro_encoded() {
+    let tags = vec![tag("Action", "Ping")];
+    let bytes = encode_tags(&tags).unwrap();
+    assert_eq!(bytes, b"\x02\x0cAction\x08Ping\x00");
+    assert_eq!(decode_tags(&bytes).unwrap(), tags);
+    assert!(encode_tags(&[]).unwrap().is_empty());
+    // A block with a negative count carries its byte size too
+    assert_eq!(
+        decode_tags(b"\x01\x18\x0cAction\x08Ping\x00").unwrap(),
+        tags
+    );
+}
+
+#[test]
+fn data_item_layout_round_trips() {
+    let mut item = DataItem::new(SignatureType::Ed25519);
+    item.target = Some([7; 32]);
+    item.tags = vec![tag("Action", "Ping")];
+    item.data = b"hi".to_vec();
+    let bytes = item.encode().unwrap();
+
+    assert_eq!(&bytes[..2], &[2, 0]);
+    // Signature and owner take 64 + 32 bytes, then target and anchor
+    assert_eq!(bytes[98], 1);
+    assert_eq!(&bytes[99..131], &[7; 32]);
+    assert_eq!(bytes[131], 0);
+    assert_eq!(&bytes[132..140], &1u64.to_le_bytes());
+    assert_eq!(&bytes[140..148], &14u64.to_le_bytes());
+    assert_eq!(&bytes[162..], b"hi");
+    assert_eq!(DataItem::decode(&bytes).unwrap(), item);
+
+    assert_eq!(
+        DataItem::decode(&bytes[..100]),
+        Err(DataItemError::Truncated("target"))
+    );
+}
+
+#[test]
+fn outbox_message_round_trips_through_lua() {
+    let lua = lua();
+    let item = render(
+        &lua,
+        r#"Handlers = require(".handlers")
+        local ans104 = require(".ans104")
+        ao.send({ Target = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw", Data = "hello", Action = "Ping" })
+        local item = ans104.decode(ans104.encode(ao.outbox.Messages[1]))
+        return {
+          item.SignatureType, #item.Signature, #item.Owner, item.Target, item.Anchor,
+          #item.Tags, item.Tags[5].name, item.Data,
+        }"#,
+    );
+    assert_eq!(
+        item,
+        "return {\n  1,\n  512,\n  512,\n  \"PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw\",\n  \
+         \"00000000000000000000000000000001\",\n  5,\n  \"Action\",\n  \"hello\",\n}\n"
+    );
+}
+
+#[test]
+fn deep_hash_folds_lists() {
+    let chunk = DeepHashChunk::List(vec![
+        DeepHashChunk::Blob(b"a".to_vec()),
+        DeepHashChunk::List(Vec::new()),
+    ]);
+    assert_eq!(
+        hex(&deep_hash(&chunk)),
+        "72d0766e7bade0bd1a8d879d92a0123fe6fc7b2c2bea27b0048c2a636487057f\
+         26df5c5d599a489027a669d1260de1a3"
+    );
+}
+
+#[test]
+fn signature_data_and_id() {
+    let mut item = DataItem::new(SignatureType::Ed25519);
+    item.target = Some([7; 32]);
+    item.tags = vec![tag("Action", "Ping")];
+    item.data = b"hi".to_vec();
+    assert_eq!(
+        hex(&item.signature_data().unwrap()),
+        "bb10a44b5092b21c1329398f0caee56246abee81ed89420a64a16dad70043f95\
+         23404d033b5252530f301bd36d1dd222"
+    );
+    assert_eq!(item.id(), "9aX9QtFqIDAnmO9u0wmXm0MAPSMg2fDo6pgxqSdZ-0s");
+}
+
+#[test]
+fn lua_deep_hash_matches_rust() {
+    let lua = lua();
+    let hashes = render(
+        &lua,
+        r#"local ans104 = require(".ans104")
+        local crypto = require(".crypto")
+        local hex = crypto.utils.hex.stringToHex
+        local msg = ao.send({ Target = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw", Action = "Ping" })
+        local signature = string.rep("\1", 512)
+        local item = ans104.encode(msg, { signature = signature })
+        return {
+          hex(ans104.deepHash({ "a", {} })),
+          crypto.digest.sha2_384("abc").asHex(),
+          #ans104.signatureData(msg),
+          ans104.signatureData(msg) == ans104.signatureData(msg, { signature = signature }),
+          ans104.id(item),
+        }"#,
+    );
+    assert_eq!(
+        hashes,
+        "return {\n  \"72d0766e7bade0bd1a8d879d92a0123fe6fc7b2c2bea27b0048c2a636487057f\
+         26df5c5d599a489027a669d1260de1a3\",\n  \
+         \"cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+         8086072ba1e7cc2358baeca134c825a7\",\n  48,\n  true,\n  \
+         \"bK841TeYTiYVJ7jK71-ZD7kUFaHbkXGYghp57SiZeXM\",\n}\n"
+    );
+}
+
+#[test]
+fn signed_items_verify() {
+    let mut item = DataItem::new(SignatureType::Ed25519);
+    item.owner = unhex("03a107bff3ce10be1d70dd18e74bc09967e4d6309ba50d5f1ddc8664125531b8");
+    item.signature = unhex(
+        "999c823dd1595f5f873f45cd8916395d497b6f5014946a8c316db7773ad90ba1\
+         f5ee5fa671c32bbb5d167e8f5d5377071c5710dbe319ef23372352ce12238403",
+    );
+    item.target = Some(
+        unhex("3dc0db889344edf0b87069519efcdfc547733444a32a489366523a80a89f852c")
+            .try_into()
+            .unwrap(),
+    );
+    item.tags = vec![tag("Action", "Ping")];
+    item.data = b"hi".to_vec();
+    assert_eq!(item.verify(), Ok(true));
+    assert_eq!(
+        item.owner_address(),
+        "Vkdap1RjR0wChd9dvyvKtz2mUTWIOem3dIGy6rEHcIw"
+    );
+
+    item.data = b"ho".to_vec();
+    assert_eq!(item.verify(), Ok(false));
+}
+
+// The item above under another signature scheme, with an owner key and signature
+// generated by Python's `cryptography`
+fn signed_item(signature_type: SignatureType, owner: &str, signature: &str) -> DataItem {
+    let mut item = DataItem::new(signature_type);
+    item.owner = unhex(owner);
+    item.signature = unhex(signature);
+    item.target = Some(
+        unhex("3dc0db889344edf0b87069519efcdfc547733444a32a489366523a80a89f852c")
+            .try_into()
+            .unwrap(),
+    );
+    item.tags = vec![tag("Action", "Ping")];
+    item.data = b"hi".to_vec();
+    item
+}
+
+// Flips the low bit of the byte at `index`
+fn tampered(bytes: &[u8], index: usize) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    bytes[index] ^= 1;
+    bytes
+}
+
+// The modulus of an RSA-4096 key, big-endian
+const RSA_OWNER: &str = "e4eb219befb7a06622ed6b187ee0700f4ad19618bd55c263671cfe6dd2a1d6cd\
+    13e2384ff726a7182744df8237d257b95ff94311bf39d8ba4542a9b6af43624c\
+    e7793be5493f2b58b50d279f3c4807ba60dd2407f447c2f7371bc3bb2cbf9854\
+    a07a7064a21048a9228a6716ef783f7e5d857a65e0a1f6d5f21c6ef77bd541dd\
+    165748579017eb43769e11d80e265faa5f83a4cedbb49bfe147ed87506f9ae67\
+    af8276c154de7a5df85385f6f9d7b7396b8ee3c84cff155e1dca7fbd0d2eaaad\
+    a56b2b8da0389464a2169fb7935afa1f48b5f426460902533702a7db4b21d3c2\
+    38e62fd209a0a0b4392779ce74ecb0240579b5811008f758e6fd3bf29fe366c6\
+    4a0ea5434ec4377972dec15ed7c42476cf193cfae9a86ec712aa1ff5e8e67dd1\
+    707aed148b95ec820b52d9361c247600bfdcf1750e3efadcdf66b9863a84031c\
+    fc8adca441b6d239e4c354babce2662b0661d649600fc16a73ea4a0001f3d18e\
+    f3e99817c4752f833cab7a1b6357091b7e0d445bbe1be8d0a4937d9a03c90d36\
+    31c68f67afd45ea5243cd5552c2bfbaf9e13dd49203d2f05d7532692b27c30e7\
+    730140cff933d9b56cf2fb6725f4f63f8eaed2ae8540d1497581c6aa2afc04b4\
+    3fe3b325adda63a75cedee77161be385990e88909794c70e7da2ad024e717058\
+    d0dce3494cc8b33a8f748592086c93b607600b6e7f0569c40d866dacb5b1db21";
+// Its RSA-PSS signatures, SHA-256 with a 32-byte salt and with an empty salt
+const RSA_SALT32: &str = "bf92797077fe907d72b5c3399656c12dd3be50f179584bb80bd5b3de8b4e2511\
+    d99fe3bc0e78783ed2e47bd0c010e892a0d4b0c516f86f065bf5f11eaf3615c3\
+    911c2bffde4345b13c1c034efe25b1d1231de867cba665b7343f2ef966fee779\
+    268d47bdc0410a5bdc7eedb7f924d5255b3cdac1218094e90d89c9460151ce81\
+    1fbaf497f3e1e76dd7a61870b3db4a9b29fca991d99ce194d5ce78a660fea509\
+    60477507ef4fa76385f420a9800549f2ccc7121756df283852d38ae4ff92f6ad\
+    005d52fd8129fb1f0d5faa8280972facd51232cd9c000a6a24ba3f7f3b022afc\
+    64552f254461596f3ffbe2aa93136f8993def12854397ae757e05b76cd89bee3\
+    dce46ff1e6654a6c45e28f5480c52c997c4be5066f8ddf2482a87a89f203d29b\
+    524d539e1fd7871653e58a05221b3ed9a7117b09afda76cb510ebbd7c5996e14\
+    cfc9ada8bb85adfe15bffaf49554878388dd5c8cd9b8c70170343d1e537c3236\
+    4f489691bbaf9cb921f8065309d37f9f0cc8dabe831d97d61d3cc6664b166663\
+    eb45da007b3565fee7212c027ca2cc0cdf3c20b034d00e0b1ccff040087556f7\
+    e2fbe73ce259b531f166ac3b21874d35c5b65ba4e723b19b5554d76331813f82\
+    b21062ee90d6e2169310334ca846262af044bd1a719b72b8dd039286078751e6\
+    17307f3e3415a8df8e2b087bc56a85bd8afd74aec82e1dd8df3562d68389cff8";
+const RSA_SALT0: &str = "c13673108674b818a9c65cefbaeea58d7164655db5c44bd68cde68f92aee9c75\
+    b7e18cab5d965a921c3caa8428625f09e4da17a14671fb9f20dc49f4435df995\
+    ae5ea3d7daa0237a5792d57664ee13d45a8ef83ead5827886e5dfa06a8351f49\
+    ad959faf2cfc663b388fc8c9f888c63487ed181b578fa45c87d0965c92c607ea\
+    8c7d6a106c30a8e5f8094d3d32b8fab713d25a01c26713efa9bedbcedd158f35\
+    5132227461c019178b14e6f2ccf83f13bcc763dd8c51ac3d5daae156e0b98ebd\
+    f496dfa43fdb37b0f6ee8640e6c1d2f5db98b17ff7cf68484b9040c60779cecb\
+    25b34cd50caeeb00ef0da16b5b00fc1423fa30b7dd1238b031fb65221082b3a1\
+    ef20cd82c25950fce5e1ca2a8cb864f4055e137dfdf7b8e37f7231ba5b2a0760\
+    d94cff33a1fd5dfc716a6a761074566ce4ed5d6f8b9652b0c49fb6e8f9c143d4\
+    2174fc3167a27c4d9c7606ef040b762b2e2ac70ef2b4c7db14cc1d39355cbd48\
+    b3643d36590568b2dfd41902738b9f61a6858a2b657d0c5b03d3840c5281fead\
+    09a88590f614dbcd3886ff9aa2a77b327fd79e40eac313fe730d5a15f30b06a3\
+    b4773e36ffeaeb99edec3f7cadcd24e8b9c5649dd048039ac9b993242ef5ffa9\
+    41818f2167fc275140dacf055a009c0a097fe76be50f5a197a80989749e1a072\
+    9dc2c88381db783b284ca59de2fb0de21a918a84058b8aaad4a8f9c6104dcf69";
+
+#[test]
+fn rsa_pss_items_verify_with_either_salt() {
+    for signature in [RSA_SALT32, RSA_SALT0] {
+        let mut item = signed_item(SignatureType::Arweave, RSA_OWNER, signature);
+        assert_eq!(item.verify(), Ok(true));
+
+        let signature = item.signature.clone();
+        item.signature = tampered(&signature, 200);
+        assert_eq!(item.verify(), Ok(false));
+        item.signature = signature;
+
+        item.data = b"ho".to_vec();
+        assert_eq!(item.verify(), Ok(false));
+    }
+}
+
+// An uncompressed secp256k1 public key, and its signature over the item as an
+// Ethereum signed message: `r || s` and the recovery byte
+const SECP_OWNER: &str = "043c7541ee764eb3966ed96a01ef7f1122c555fcd77a87878a52e71149815b7a\
+    e4e80da4052008952da49b91cc6ec169e194d518f0bf22fe0e1d860ad0a4e567\
+    6c";
+const SECP_SIGNATURE: &str = "9762f758619a5c89a802f71c4fe00e23f5e55e0d609fde1c7f4a4256f2726d7f\
+    22a6881ac5fa9c4dc6383b85f15faf3cceb0e684fd300143f26c770ac8c8a4ba\
+    1b";
+
+#[test]
+fn secp256k1_items_verify_with_or_without_recovery_byte() {
+    let mut item = signed_item(SignatureType::Ethereum, SECP_OWNER, SECP_SIGNATURE);
+    assert_eq!(item.verify(), Ok(true));
+
+    let signature = item.signature.clone();
+    item.signature.truncate(64);
+    assert_eq!(item.verify(), Ok(true));
+    item.signature = tampered(&signature, 40);
+    assert_eq!(item.verify(), Ok(false));
+    item.signature = signature;
+
+    item.data = b"ho".to_vec();
+    assert_eq!(item.verify(), Ok(false));
+}
+
+// RFC 8032 section 7.1, tests 1 to 3: public key, message and signature
+const ED25519_VECTORS: [(&str, &str, &str); 3] = [
+    (
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "",
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+         5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+    ),
+    (
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "72",
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+         085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+    ),
+    (
+        "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+        "af82",
+        "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+         18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+    ),
+];
+
+#[test]
+fn ed25519_rfc8032_vectors() {
+    for (public_key, message, signature) in ED25519_VECTORS {
+        let (public_key, message, signature) =
+            (unhex(public_key), unhex(message), unhex(signature));
+        assert!(verify_ed25519(&public_key, &message, &signature));
+        assert!(!verify_ed25519(&public_key, b"other", &signature));
+        assert!(!verify_ed25519(
+            &public_key,
+            &message,
+            &tampered(&signature, 10)
+        ));
+        assert!(!verify_ed25519(
+            &tampered(&public_key, 0),
+            &message,
+            &signature
+        ));
+    }
+}
+
+#[test]
+fn ed25519_rejects_non_canonical_scalars_and_bad_keys() {
+    let (public_key, _, signature) = ED25519_VECTORS[0];
+    let public_key = unhex(public_key);
+    // Test 1's signature with L added to s, which satisfies the group equation
+    // all the same
+    let high_s = unhex(
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+         4c8c7872aa064e049dbb3013fbf29380d25bf5f0595bbe24655141438e7a101b",
+    );
+    assert!(!verify_ed25519(&public_key, b"", &high_s));
+
+    // y = 2 has no x on the curve
+    let mut off_curve = vec![0; 32];
+    off_curve[0] = 2;
+    assert!(!verify_ed25519(&off_curve, b"", &unhex(signature)));
+
+    // y = p + 1 encodes the identity non-canonically, under which R = B, s = 1 would
+    // verify for any message
+    let mut non_canonical = vec![0xff; 32];
+    non_canonical[0] = 0xee;
+    non_canonical[31] = 0x7f;
+    let mut forged = unhex("5866666666666666666666666666666666666666666666666666666666666666");
+    forged.push(1);
+    forged.resize(64, 0);
+    assert!(!verify_ed25519(&non_canonical, b"anything", &forged));
+}
+
+// The item above signed as a Solana item with RFC 8032 test 1's key; Solana items
+// commit to signature type 4 in their deep hash
+const SOLANA_OWNER: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+const SOLANA_SIGNATURE: &str = "abecf9e4934356f6851b394887aff0f9d3ca5d1199b1d4de0952ff953cb3268e\
+    89fac4c82520ff909261bafef996ab41bcba51040fc2d4a0e55ecc6defc18a0f";
+
+#[test]
+fn solana_items_verify_under_their_own_type() {
+    let mut item = signed_item(SignatureType::Solana, SOLANA_OWNER, SOLANA_SIGNATURE);
+    assert_eq!(item.verify(), Ok(true));
+    item.signature_type = SignatureType::Ed25519;
+    assert_eq!(item.verify(), Ok(false));
+}
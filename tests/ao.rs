@@ -0,0 +1,351 @@
+mod common;
+
+use common::{assert_golden, lua, render};
+
+/// A valid process ID to send to.
+const TARGET: &str = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw";
+
+#[test]
+fn send_builds_message_and_outbox_entry() {
+    let lua = lua();
+    lua.load(r#"Handlers = require(".handlers")"#)
+        .exec()
+        .unwrap();
+    let message = render(
+        &lua,
+        &format!(r#"return ao.send({{ Target = "{TARGET}", Data = "hello", Action = "Ping" }})"#),
+    );
+    assert_golden("send_message", &message);
+    assert_golden("send_outbox", &render(&lua, "return ao.outbox"));
+}
+
+#[test]
+fn send_without_handlers_skips_outbox() {
+    let lua = lua();
+    let outbox = render(
+        &lua,
+        &format!(
+            r#"ao.send({{ Target = "{TARGET}", Tags = {{ {{ name = "Action", value = "Ping" }} }} }})
+            return ao.outbox.Messages"#
+        ),
+    );
+    assert_eq!(outbox, "return {}\n");
+}
+
+#[test]
+fn spawn_builds_process_and_outbox_entry() {
+    let lua = lua();
+    lua.load(r#"Handlers = require(".handlers")"#)
+        .exec()
+        .unwrap();
+    let spawn = render(
+        &lua,
+        r#"return ao.spawn("CHILD-MODULE", { Data = "init", Name = "child" })"#,
+    );
+    assert_golden("spawn_message", &spawn);
+    assert_golden("spawn_outbox", &render(&lua, "return ao.outbox.Spawns"));
+}
+
+#[test]
+fn result_collects_outbox() {
+    let lua = lua();
+    lua.load(r#"Handlers = require(".handlers")"#)
+        .exec()
+        .unwrap();
+    let result = render(
+        &lua,
+        &format!(
+            r#"ao.send({{ Target = "{TARGET}", Data = "hello" }})
+            ao.assign({{ Processes = {{ "P1" }}, Message = "M1" }})
+            return ao.result({{}})"#
+        ),
+    );
+    assert_golden("result", &result);
+}
+
+#[test]
+fn result_reports_errors_only() {
+    let lua = lua();
+    let result = render(
+        &lua,
+        &format!(
+            r#"ao.send({{ Target = "{TARGET}", Data = "hello" }})
+            return {{ ao.result({{ Error = "boom" }}), (function()
+              ao.outbox.Error = "outbox boom"
+              return ao.result({{}})
+            end)() }}"#
+        ),
+    );
+    assert_golden("result_error", &result);
+}
+
+// Builds the same message with its fields inserted in a rotated order, passes it to
+// `call`, and lists the resulting tags as `name=value`
+fn tag_list(call: &str, rotation: usize) -> String {
+    let lua = lua();
+    lua.load(r#"Handlers = require(".handlers")"#)
+        .exec()
+        .unwrap();
+    lua.load(format!(
+        r#"local rotation = {rotation}
+        local fields = {{ {{ "Zeta", "1" }}, {{ "Action", "Ping" }}, {{ "Quantity", "5" }} }}
+        local tags = {{ {{ "Recipient", "BOB" }}, {{ "Beta", "2" }}, {{ "Alpha", "3" }} }}
+        local msg = {{ Target = "{TARGET}", Tags = {{}} }}
+        for i = 1, #fields do
+          local field = fields[(i + rotation) % #fields + 1]
+          msg[field[1]] = field[2]
+        end
+        for i = 1, #tags do
+          local tag = tags[(i + rotation) % #tags + 1]
+          msg.Tags[tag[1]] = tag[2]
+        end
+        local list = {{}}
+        for i, tag in ipairs(({call})(msg).Tags) do
+          list[i] = tag.name .. "=" .. tag.value
+        end
+        return table.concat(list, ",")"#
+    ))
+    .eval()
+    .unwrap()
+}
+
+#[test]
+fn send_orders_custom_tags_by_name() {
+    for rotation in 0..6 {
+        assert_eq!(
+            tag_list("ao.send", rotation),
+            "Data-Protocol=ao,Variant=ao.TN.1,Type=Message,Reference=1,\
+             Action=Ping,Quantity=5,Zeta=1,Alpha=3,Beta=2,Recipient=BOB"
+        );
+    }
+}
+
+#[test]
+fn spawn_orders_custom_tags_by_name() {
+    for rotation in 0..6 {
+        assert_eq!(
+            tag_list(
+                r#"function(msg) return ao.spawn("CHILD-MODULE", msg) end"#,
+                rotation
+            ),
+            "Data-Protocol=ao,Variant=ao.TN.1,Type=Process,From-Process=PROCESS,\
+             From-Module=MODULE,Module=CHILD-MODULE,Reference=1,\
+             Action=Ping,Quantity=5,Zeta=1,Alpha=3,Beta=2,Recipient=BOB"
+        );
+    }
+}
+
+#[test]
+fn send_keeps_tag_list_order() {
+    let lua = lua();
+    let tags = render(
+        &lua,
+        &format!(
+            r#"local message = ao.send({{
+              Target = "{TARGET}",
+              Tags = {{ {{ name = "Zeta", value = "1" }}, {{ name = "Alpha", value = "2" }} }},
+            }})
+            return {{ message.Tags[5].name, message.Tags[6].name }}"#
+        ),
+    );
+    assert_eq!(tags, "return {\n  \"Zeta\",\n  \"Alpha\",\n}\n");
+}
+
+// Sends through a normalized, enriched copy of an incoming message and lists the
+// outgoing message as `Target` followed by its `name=value` tags
+fn outgoing(script: &str) -> String {
+    let lua = lua();
+    lua.load(format!(
+        r#"Handlers = require(".handlers")
+        local msg = ao.enrich(ao.normalize({{
+          Id = "MSG", From = "ALICE", Owner = "ALICE", Reference = "7", Data = "hi",
+          Tags = {{ {{ name = "Action", value = "Ping" }}, {{ name = "Reference", value = "7" }} }},
+        }}))
+        {script}
+        local sent = ao.outbox.Messages[1]
+        local list = {{ sent.Target }}
+        for _, tag in ipairs(sent.Tags) do
+          list[#list + 1] = tag.name .. "=" .. tag.value
+        end
+        return table.concat(list, ",")"#
+    ))
+    .eval()
+    .unwrap()
+}
+
+#[test]
+fn reply_answers_the_sender_with_its_reference() {
+    assert_eq!(
+        outgoing(r#"msg.reply({ Data = "pong", Action = "Pong" })"#),
+        "ALICE,Data-Protocol=ao,Variant=ao.TN.1,Type=Message,Reference=1,\
+         Action=Pong,X-Origin=,X-Reference=7"
+    );
+}
+
+#[test]
+fn reply_prefers_reply_to() {
+    assert_eq!(
+        outgoing(
+            r#"msg["Reply-To"] = "CAROL"
+            Handlers.utils.reply("pong")(msg)"#
+        ),
+        "CAROL,Data-Protocol=ao,Variant=ao.TN.1,Type=Message,Reference=1,\
+         X-Origin=,X-Reference=7"
+    );
+}
+
+#[test]
+fn forward_sends_sanitized_copy() {
+    assert_eq!(
+        outgoing(r#"msg.forward("BOB", { Note = "fyi" })"#),
+        "BOB,Data-Protocol=ao,Variant=ao.TN.1,Type=Message,Reference=1,\
+         Action=Ping,Forwarded-By=PROCESS,Note=fyi,X-Origin=ALICE,X-Reference=7"
+    );
+}
+
+#[test]
+fn send_records_warnings_and_sends_anyway() {
+    let lua = lua();
+    let warnings = render(
+        &lua,
+        r#"Handlers = require(".handlers")
+        ao.send({ Target = "TARGET", Type = "Spoofed", Big = string.rep("x", 3073) })
+        return { #ao.outbox.Messages, ao.result({}).Warnings }"#,
+    );
+    assert_eq!(
+        warnings,
+        "return {\n  1,\n  {\n    \
+         \"ao.send: Target must be a 43-character base64url ID, got \\\"TARGET\\\"\",\n    \
+         \"ao.send: tag Big value is 3073 bytes, at most 3072 allowed\",\n    \
+         \"ao.send: tag Type is reserved, ao sets it\",\n  },\n}\n"
+    );
+}
+
+#[test]
+fn strict_validation_rejects_without_side_effects() {
+    let lua = lua();
+    let (error, reference, sent): (String, i64, i64) = lua
+        .load(format!(
+            r#"Handlers = require(".handlers")
+            ao.validation = "strict"
+            local tags = {{}}
+            for i = 1, 125 do tags[i] = {{ name = "T" .. i, value = "v" }} end
+            local ok, err = pcall(ao.send, {{ Target = "{TARGET}", Tags = tags }})
+            assert(not ok)
+            return tostring(err), ao.reference, #ao.outbox.Messages"#
+        ))
+        .eval()
+        .unwrap();
+    assert!(
+        error.contains("ao.send: 129 tags, at most 128 allowed"),
+        "{}",
+        error
+    );
+    assert_eq!((reference, sent), (0, 0));
+}
+
+#[test]
+fn strict_validation_checks_spawn_tags() {
+    let lua = lua();
+    let error: String = lua
+        .load(
+            r#"ao.validation = "strict"
+            local ok, err = pcall(ao.spawn, "CHILD-MODULE", { [string.rep("n", 1025)] = "v" })
+            return tostring(err)"#,
+        )
+        .eval()
+        .unwrap();
+    assert!(error.contains("ao.spawn: tag name nnnn"), "{}", error);
+    assert!(
+        error.contains("is 1025 bytes, at most 1024 allowed"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn valid_messages_and_off_mode_leave_no_warnings() {
+    let lua = lua();
+    let warnings = render(
+        &lua,
+        &format!(
+            r#"Handlers = require(".handlers")
+            ao.send({{ Target = "{TARGET}", Action = "Ping" }})
+            ao.spawn("CHILD-MODULE", {{ Name = "child" }})
+            ao.validation = "off"
+            ao.send({{ Target = "TARGET", Type = "Spoofed" }})
+            return {{ #ao.outbox.Messages, ao.outbox.Warnings == nil }}"#
+        ),
+    );
+    assert_eq!(warnings, "return {\n  2,\n  true,\n}\n");
+}
+
+// An Ed25519 key and its signature over a message to TARGET with an Action = Ping tag
+// and Data = "hi", computed with Python's `cryptography`
+const PUBLIC_KEY: &str = "A6EHv_POEL4dcN0Y50vAmWfk1jCbpQ1fHdyGZBJVMbg";
+const SIGNATURE: &str =
+    "mZyCPdFZX1-HP0XNiRY5XUl7b1AUlGqMMW23dzrZC6H17l-mccMru10Wfo9dU3cHHFcQ2-MZ7yM3I1LOEiOEAw";
+const SIGNER: &str = "Vkdap1RjR0wChd9dvyvKtz2mUTWIOem3dIGy6rEHcIw";
+
+#[test]
+fn signature_trust_verifies_the_signer() {
+    let lua = lua();
+    let trusted = render(
+        &lua,
+        &format!(
+            r#"ao.authorities = {{ "{SIGNER}" }}
+            local function msg(fields)
+              local m = {{
+                Target = "{TARGET}", From = "{SIGNER}", Owner = "{SIGNER}", Data = "hi",
+                Tags = {{ {{ name = "Action", value = "Ping" }} }},
+                Signature = "{SIGNATURE}", ["Public-Key"] = "{PUBLIC_KEY}",
+              }}
+              for k, v in pairs(fields) do m[k] = v end
+              return m
+            end
+            local unsigned = msg({{}})
+            unsigned.Signature, unsigned["Public-Key"] = nil, nil
+            local before = ao.isTrusted(unsigned)
+            ao.trust = "signature"
+            return {{
+              before,
+              ao.isTrusted(msg({{}})),
+              ao.isTrusted(msg({{ Owner = "SOMEONE-ELSE" }})),
+              ao.isTrusted(msg({{ Data = "tampered" }})),
+              ao.isTrusted(unsigned),
+            }}"#
+        ),
+    );
+    assert_eq!(
+        trusted,
+        "return {\n  true,\n  true,\n  false,\n  false,\n  false,\n}\n"
+    );
+}
+
+// RFC 8032 test 1's key and its signature over the same message as a Solana item,
+// whose deep hash commits to signature type 4
+const SOLANA_PUBLIC_KEY: &str = "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo";
+const SOLANA_SIGNATURE: &str =
+    "q-z55JNDVvaFGzlIh6_w-dPKXRGZsdTeCVL_lTyzJo6J-sTIJSD_kJJhuv75lqtBvLpRBA_C1KDlXsxt78GKDw";
+const SOLANA_SIGNER: &str = "If4x36FUomFia_hUBG_SJxt77UtqvkWqWId-9H-XIbk";
+
+#[test]
+fn signature_trust_accepts_solana_signers() {
+    let lua = lua();
+    let trusted = render(
+        &lua,
+        &format!(
+            r#"ao.authorities = {{ "{SOLANA_SIGNER}" }}
+            ao.trust = "signature"
+            local msg = {{
+              Target = "{TARGET}", From = "{SOLANA_SIGNER}", Data = "hi",
+              Tags = {{ {{ name = "Action", value = "Ping" }} }},
+              Signature = "{SOLANA_SIGNATURE}", ["Public-Key"] = "{SOLANA_PUBLIC_KEY}",
+            }}
+            local trusted = ao.isTrusted(msg)
+            msg.Data = "tampered"
+            return {{ trusted, ao.isTrusted(msg) }}"#
+        ),
+    );
+    assert_eq!(trusted, "return {\n  true,\n  false,\n}\n");
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{assert_golden, lua, render};
+
+#[test]
+fn assignables_match_assignments() {
+    let lua = lua();
+    let state = render(
+        &lua,
+        r#"require(".assignment").init(ao)
+        ao.addAssignable("credit", { Action = "Credit" })
+        ao.addAssignable({ From = "FRIEND" })
+        -- Adding under an existing name replaces its pattern
+        ao.addAssignable("credit", { Action = "Credit-Notice" })
+        local result = {
+          count = #ao.assignables,
+          name = ao.assignables[1].name,
+          isAssignment = {
+            ao.isAssignment({ Target = "PROCESS" }),
+            ao.isAssignment({ Target = "OTHER" }),
+          },
+          isAssignable = {
+            ao.isAssignable({ Action = "Credit-Notice" }),
+            ao.isAssignable({ From = "FRIEND" }),
+            ao.isAssignable({ Action = "Credit" }),
+          },
+        }
+        ao.removeAssignable("credit")
+        result.afterRemove = #ao.assignables
+        return result"#,
+    );
+    assert_golden("assignment", &state);
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::{lua, render};
+
+const SETUP: &str = r#"
+base64 = require(".base64")
+function failure(f, ...)
+  local ok, err = pcall(f, ...)
+  assert(not ok, "expected an error")
+  return tostring(err)
+end
+-- Feeds `input` to a streaming encoder or decoder in chunks of `size` bytes
+function stream(coder, input, size)
+  local out = {}
+  for i = 1, #input, size do
+    out[#out + 1] = coder:update(input:sub(i, i + size - 1))
+  end
+  out[#out + 1] = coder:finish()
+  return table.concat(out)
+end
+"#;
+
+#[test]
+fn rfc4648_vectors() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local out = {}
+        for _, input in ipairs({ "", "f", "fo", "foo", "foob", "fooba", "foobar" }) do
+          local encoded = base64.encode(input)
+          assert(base64.decode(encoded, { strict = true }) == input, input)
+          out[#out + 1] = encoded
+        end
+        return out"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"\",\n  \"Zg==\",\n  \"Zm8=\",\n  \"Zm9v\",\n  \"Zm9vYg==\",\n  \
+         \"Zm9vYmE=\",\n  \"Zm9vYmFy\",\n}\n"
+    );
+}
+
+#[test]
+fn url_safe_alphabet_and_padding_options() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local url = { alphabet = "url" }
+        return {
+          base64.encode("\251\255\254"),
+          base64.encode("\251\255\254", url),
+          base64.encode("\255\254"),
+          base64.encode("\255\254", url),
+          base64.encode("\255\254", { alphabet = "url", padding = true }),
+          base64.encode("\255\254", { padding = false }),
+          base64.decode("__4", { alphabet = "url", strict = true }) == "\255\254",
+          base64.decode("//4=", { strict = true }) == "\255\254",
+          base64.decode("-__-") == "\251\255\254",
+          base64.decode("Zm 9v\nYg") == "foob",
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"+//+\",\n  \"-__-\",\n  \"//4=\",\n  \"__4\",\n  \"__4=\",\n  \"//4\",\n  \
+         true,\n  true,\n  true,\n  true,\n}\n"
+    );
+}
+
+#[test]
+fn streaming_matches_one_shot_at_every_chunk_size() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let mismatches = render(
+        &lua,
+        r#"local input = {}
+        for i = 0, 99 do input[#input + 1] = string.char((i * 37) % 256) end
+        input = table.concat(input)
+        local mismatches = {}
+        for _, options in ipairs({ {}, { alphabet = "url" } }) do
+          for length = 0, #input, 11 do
+            local data = input:sub(1, length)
+            local encoded = base64.encode(data, options)
+            for size = 1, 7 do
+              local streamed = stream(base64.encoder(options), data, size)
+              local decoded = stream(base64.decoder(options), encoded, size)
+              if streamed ~= encoded or decoded ~= data then
+                mismatches[#mismatches + 1] = length .. "/" .. size
+              end
+            end
+          end
+        end
+        local decoder = base64.decoder({ strict = true })
+        local split = decoder:update("Zm9vY") .. decoder:update("g=") .. decoder:update("=")
+        return { mismatches, split .. decoder:finish() }"#,
+    );
+    assert_eq!(mismatches, "return {\n  {},\n  \"foob\",\n}\n");
+}
+
+#[test]
+fn strict_decoding_rejects_invalid_input() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let errors: Vec<String> = lua
+        .load(
+            r#"local strict = { strict = true }
+            local decoder = base64.decoder(strict)
+            return {
+              failure(base64.decode, "Zm9v!", strict),
+              failure(base64.decode, "Zm9vY", { alphabet = "url", strict = true }),
+              failure(base64.decode, "Zg=", strict),
+              failure(base64.decode, "Z===", strict),
+              failure(base64.decode, "Zg==Zg==", strict),
+              failure(base64.decode, "Zg", strict),
+              failure(base64.decode, "Zh==", strict),
+              failure(base64.decode, "-__-", strict),
+              failure(base64.encode, "", { alphabet = "hex" }),
+              failure(decoder.update, decoder, "Zm9v Zg=="),
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    let expected = [
+        "invalid base64 byte 0x21 at offset 4",
+        "invalid base64 length",
+        "invalid base64 padding",
+        "invalid base64 padding",
+        "invalid base64 padding",
+        "invalid base64 padding",
+        "invalid base64 trailing bits",
+        "invalid base64 byte 0x2d at offset 0",
+        "unknown base64 alphabet 'hex', expected 'standard' or 'url'",
+        "invalid base64 byte 0x20 at offset 4",
+    ];
+    assert_eq!(errors.len(), expected.len());
+    for (error, expected) in errors.iter().zip(expected) {
+        assert!(error.contains(expected), "{} lacks {}", error, expected);
+    }
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use common::lua;
+
+const SETUP: &str = r#"
+bint = require(".bint")(256)
+bint64 = require(".bint")(64)
+function failure(f, ...)
+  local ok, err = pcall(f, ...)
+  assert(not ok, "expected an error")
+  return tostring(err)
+end
+"#;
+
+fn strings(source: &str) -> Vec<String> {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    lua.load(source).eval().unwrap()
+}
+
+#[test]
+fn arithmetic_wraps_at_the_width() {
+    let results = strings(
+        r#"return {
+          tostring(bint(2) ^ 200 + 1),
+          tostring(bint(2) ^ 128 * bint(2) ^ 127),
+          tostring(bint.maxinteger() + 1 == bint.mininteger()),
+          tostring(bint64.maxinteger() + 1),
+          tostring(bint64(0) - 1),
+          tostring(-bint64.mininteger()),
+          tostring(bint.checkedadd(bint.maxinteger(), 1)),
+          tostring(bint.checkedmul(bint(3), 4)),
+          tostring(bint(7) + 0.5),
+          tostring(bint(7) / 2),
+          tostring(bint.ipow(3, 5)),
+          tostring(bint.upowmod(4, 13, 497)),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        [
+            "1606938044258990275541962092341162602522202993782792835301377",
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819968",
+            "true",
+            "-9223372036854775808",
+            "-1",
+            "-9223372036854775808",
+            "nil",
+            "12",
+            "7.5",
+            "3.5",
+            "243",
+            "445",
+        ]
+    );
+}
+
+#[test]
+fn signed_division_rounds_like_lua() {
+    let results = strings(
+        r#"local q, r = bint.idivmod(7, -2)
+        local tq, tr = bint.tdivmod(-7, 2)
+        return {
+          tostring(bint(-7) // 2),
+          tostring(bint(-7) % 2),
+          tostring(q), tostring(r),
+          tostring(tq), tostring(tr),
+          tostring(bint.udiv(bint64(-1), 2)),
+          tostring(bint.umod(bint64(-1), 10)),
+          failure(function() return bint(1) // 0 end),
+          failure(bint.tdiv, 1, 0),
+          failure(function() return bint(7) % 0 end),
+          failure(bint.upowmod, 4, 13, 0),
+        }"#,
+    );
+    assert_eq!(
+        &results[..8],
+        [
+            "-4",
+            "1",
+            "-4",
+            "-1",
+            "-3",
+            "-1",
+            "9223372036854775807",
+            "5"
+        ]
+    );
+    assert!(
+        results[8].contains("attempt to perform 'n//0'"),
+        "{}",
+        results[8]
+    );
+    assert!(
+        results[9].contains("attempt to perform integer division by zero"),
+        "{}",
+        results[9]
+    );
+    for error in &results[10..] {
+        assert!(error.contains("attempt to perform 'n%0'"), "{}", error);
+    }
+}
+
+#[test]
+fn comparisons_are_signed_unless_asked() {
+    let results = strings(
+        r#"return {
+          tostring(bint(-1) < bint(1)),
+          tostring(bint.ult(-1, 1)),
+          tostring(bint.ult(1, -1)),
+          tostring(bint.ule(5, 5)),
+          tostring(bint(3) == bint(3)),
+          tostring(bint(3) <= 3.5),
+          tostring(bint(4) <= 3.5),
+          tostring(bint.max(-5, 2)),
+          tostring(bint.min(-5, 2)),
+          tostring(bint.iszero(bint.zero())),
+          tostring(bint.isneg(-3)),
+          tostring(bint.isodd(bint(7))),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        [
+            "true", "false", "true", "true", "true", "true", "false", "2", "-5", "true", "true",
+            "true"
+        ]
+    );
+}
+
+#[test]
+fn converts_to_and_from_strings_and_numbers() {
+    let results = strings(
+        r#"return {
+          tostring(bint("0b101")),
+          tostring(bint("-0x10")),
+          tostring(bint.frombase("zz", 36)),
+          tostring(bint.frombase("12", 2)),
+          bint.tobase(bint(255), 2),
+          bint.tobase(bint(-1), 16),
+          bint64.tobase(bint64.fromstring("0xffffffffffffffff"), 16),
+          bint.tostring(bint64.fromstring("0xffffffffffffffff")),
+          tostring(bint.tonumber(bint(2) ^ 70) == 2 ^ 70),
+          tostring(bint.tointeger(bint(2) ^ 70)),
+          tostring(bint.tointeger(bint(-5))),
+          tostring(bint(1e20)),
+          tostring(bint.frominteger(2.9)),
+          tostring(bint(2):tonumber()),
+          tostring(bint(5).bits),
+          failure(bint, 2.5),
+          failure(function() return bint(1) + bint64(1) end),
+        }"#,
+    );
+    assert_eq!(
+        &results[..15],
+        [
+            "5",
+            "-16",
+            "1295",
+            "nil",
+            "11111111",
+            "f".repeat(64).as_str(),
+            "ffffffffffffffff",
+            "-1",
+            "true",
+            "nil",
+            "-5",
+            "100000000000000000000",
+            "2",
+            "2.0",
+            "256",
+        ]
+    );
+    assert!(
+        results[15].contains("value of type 'number' cannot be converted to bint"),
+        "{}",
+        results[15]
+    );
+    assert!(
+        results[16].contains("cannot mix a 64-bit bint with a 256-bit bint"),
+        "{}",
+        results[16]
+    );
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{assert_golden, lua, render};
+
+const SETUP: &str = r#"
+Prompt = function() return "aos> " end
+Inbox = {}
+BOOT = require(".boot")(ao)
+"#;
+
+#[test]
+fn boot_evaluates_message_data() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let state = render(
+        &lua,
+        r#"BOOT({ Tags = { ["On-Boot"] = "Data" }, Data = "Booted = true", From = "OWNER" })
+        return { booted = Booted, inbox = #Inbox }"#,
+    );
+    assert_golden("boot_data", &state);
+}
+
+#[test]
+fn boot_loads_transaction_from_weavedrive() {
+    let root = std::env::temp_dir().join(format!("ao-rust-boot-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("data")).unwrap();
+    std::fs::write(root.join("data/BOOT-TX"), "BootedFrom = 'weavedrive'").unwrap();
+
+    let lua = lua();
+    lua.globals().set("ROOT", root.to_str().unwrap()).unwrap();
+    lua.load(SETUP).exec().unwrap();
+    // Serve the WeaveDrive paths from the temporary directory
+    let state = render(
+        &lua,
+        r#"local open = io.open
+        io.open = function(path, mode) return open(ROOT .. path, mode) end
+        BOOT({ Tags = { ["On-Boot"] = "BOOT-TX" }, From = "OWNER" })
+        BOOT({ Tags = { ["On-Boot"] = "MISSING-TX" }, From = "OWNER" })
+        return { bootedFrom = BootedFrom, inbox = #Inbox }"#,
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_golden("boot_weavedrive", &state);
+}
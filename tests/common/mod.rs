@@ -0,0 +1,92 @@
+//! Shared setup for the host integration tests, which run the modules in a vendored
+//! Lua 5.3: `cargo test --no-default-features --features host`.
+//!
+//! Results are rendered with the crate's `dump` module (sorted keys, functions
+//! dropped) and compared against `tests/golden/<name>.lua`. These are snapshot tests:
+//! the golden files hold this crate's own output, and `UPDATE_GOLDEN=1` rewrites them
+//! after an intended change, so review their diff like any other code. The `send_*`,
+//! `spawn_*` and `result*` goldens are also what the reference `ao.lua` renders for
+//! the same scripts. The `boot_*` and `eval_*` goldens are only this crate's output:
+//! there is no reference boot or eval module under `tests/reference/` to capture them
+//! from. Agreement with the reference modules is checked by `tests/conformance.rs`.
+#![allow(dead_code)]
+
+use mlua::prelude::*;
+use std::path::PathBuf;
+
+const PRELUDE: &str = r#"
+ENV = {
+  Process = {
+    Id = "PROCESS",
+    Owner = "OWNER",
+    Tags = {
+      { name = "Module", value = "MODULE" },
+      { name = "Authority", value = "AUTHORITY" },
+    },
+  },
+  Module = { Id = "MODULE", Tags = {} },
+}
+
+-- Copies a value without its functions so it can be dumped
+function snapshot(value, seen)
+  if type(value) ~= "table" then return value end
+  seen = seen or {}
+  if seen[value] then return "<cycle>" end
+  seen[value] = true
+  local copy = {}
+  for k, v in pairs(value) do
+    if type(v) ~= "function" then copy[snapshot(k, seen)] = snapshot(v, seen) end
+  end
+  seen[value] = nil
+  return copy
+end
+
+-- Strips the ANSI colors `stringify` puts around values
+function plain(text)
+  return (text:gsub("\27%[%d+m", ""))
+end
+
+function render(value)
+  return require(".dump").dump(snapshot(value))
+end
+"#;
+
+/// A fresh Lua state with every module preloaded and `ao` initialized from `ENV`.
+pub fn lua() -> Lua {
+    let lua = Lua::new();
+    ao_rust::preload(&lua).expect("preload modules");
+    lua.load(PRELUDE)
+        .set_name("=prelude")
+        .exec()
+        .expect("load prelude");
+    lua.load(r#"ao = require(".ao"); ao.init(ENV)"#)
+        .set_name("=init")
+        .exec()
+        .expect("initialize ao");
+    lua
+}
+
+/// Runs a chunk that returns a value and renders that value.
+pub fn render(lua: &Lua, source: &str) -> String {
+    let value: LuaValue = lua
+        .load(source)
+        .set_name("=test")
+        .eval()
+        .unwrap_or_else(|e| panic!("script failed: {}", e));
+    let render: LuaFunction = lua.globals().get("render").unwrap();
+    render.call(value).expect("render value")
+}
+
+/// Compares `actual` with the golden file `tests/golden/<name>.lua`.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.lua", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).expect("write golden file");
+        return;
+    }
+    let expected =
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e));
+    assert_eq!(actual, expected, "output differs from {}", path.display());
+}
@@ -0,0 +1,428 @@
+-- Differential cases for tests/conformance.rs. Each group generates inputs, runs
+-- them through the Rust module and the reference module from tests/reference/,
+-- and reports every case where the two disagree.
+--
+-- The driver returns `function(sources, group)`, where `sources` maps module names
+-- (".utils", ...) to reference source. It returns a list of divergence reports.
+
+local CASES = 200
+
+-- Reference modules get their own environment so that ao.lua starts from a blank
+-- `ao` instead of adopting the Rust module's state
+local function referenceLoader(sources)
+  local loaded = {}
+  local env = setmetatable({}, {
+    __index = function(_, key)
+      if key == "ao" then return nil end
+      return _G[key]
+    end
+  })
+  function env.require(name)
+    if loaded[name] == nil then
+      local source = assert(sources[name], "no reference source for " .. name)
+      loaded[name] = assert(load(source, "=reference/" .. name:sub(2) .. ".lua", "t", env))()
+    end
+    return loaded[name]
+  end
+  return env.require
+end
+
+-- A small LCG, so every run (and every Lua build) sees the same cases
+local seed = 1
+local function random(n)
+  seed = (seed * 1103515245 + 12345) % 2147483648
+  return seed // 65536 % n + 1
+end
+
+local function pick(list) return list[random(#list)] end
+
+-- Functions are reported by name
+local NAMED = {}
+local function named(name, fn)
+  NAMED[fn] = name
+  return fn
+end
+
+local WORDS = { "Ping", "Transfer", "Balance", "Credit-Notice", "alpha", "Beta", "100", "42", "x y", "" }
+local NAMES = {
+  "Action", "Quantity", "Recipient", "Name", "Ticker", "X-Reference", "Reference",
+  "From-Process", "Type", "Data", "Owner", "Timestamp", "Signature", "Id", "Target"
+}
+local PATTERNS = { "_", "%d+", "^%a+$", "Tr.*", "[Pp]ing", "^$", "Ping", "100" }
+
+local PREDICATES = {
+  named("isString", function(v) return type(v) == "string" end),
+  named("isPositive", function(v) return type(v) == "number" and v > 0 end),
+  named("identity", function(v) return v end),
+  named("isNumeric", function(v) return tonumber(v) ~= nil end),
+}
+
+local UNARY = {
+  named("tostring", function(v) return tostring(v) end),
+  named("upper", function(v) return string.upper(tostring(v)) end),
+  named("length", function(v) return #tostring(v) end),
+  named("double", function(v) return (tonumber(v) or 0) * 2 end),
+}
+
+local gen = {}
+
+function gen.scalar()
+  local r = random(4)
+  if r == 1 then return pick(WORDS) end
+  if r == 2 then return random(100) - 50 end
+  if r == 3 then return (random(1000) - 500) / 8 end
+  return random(2) == 1
+end
+
+function gen.value(depth)
+  if depth > 0 and random(4) == 1 then return gen.table(depth - 1) end
+  return gen.scalar()
+end
+
+function gen.array(depth, item)
+  local t = {}
+  for i = 1, random(6) - 1 do t[i] = (item or gen.value)(depth) end
+  return t
+end
+
+function gen.map(depth)
+  local t = {}
+  for _ = 1, random(5) - 1 do t[pick(NAMES)] = gen.value(depth) end
+  return t
+end
+
+-- Arrays, maps, and the shapes in between: mixed, sparse, and odd numeric keys
+function gen.table(depth)
+  local r = random(6)
+  if r <= 2 then return gen.array(depth) end
+  if r == 3 then return gen.map(depth) end
+  local t = gen.array(depth)
+  if r == 4 then
+    for k, v in pairs(gen.map(depth)) do t[k] = v end
+  elseif r == 5 then
+    t[#t + 2] = gen.value(depth)
+  else
+    t[pick({ 0, -1, 1.5 })] = gen.value(depth)
+  end
+  return t
+end
+
+function gen.strings()
+  return gen.array(0, function() return pick(WORDS) end)
+end
+
+-- A message as the handlers see it after normalization: every field is mirrored
+-- in the Tags map. Also returns the field names, in the order they were added.
+function gen.message()
+  local msg, names = { Tags = {} }, {}
+  for i = 1, random(5) do
+    local name, value = pick(NAMES), pick(WORDS)
+    msg[name] = value
+    msg.Tags[name] = value
+    names[i] = name
+  end
+  return msg, names
+end
+
+-- A message a process hands to ao.send or ao.spawn, with tags given in the root,
+-- as a list or as a map
+function gen.outgoing()
+  local msg = { Target = pick(WORDS) }
+  if random(2) == 1 then msg.Data = pick(WORDS) end
+  for _ = 1, random(3) - 1 do msg[pick(NAMES)] = pick(WORDS) end
+  local r = random(3)
+  if r == 1 then
+    msg.Tags = gen.array(0, function() return { name = pick(NAMES), value = pick(WORDS) } end)
+  elseif r == 2 then
+    msg.Tags = {}
+    for _ = 1, random(3) do msg.Tags[pick(NAMES)] = pick(WORDS) end
+  end
+  return msg
+end
+
+function gen.pattern()
+  local r = random(5)
+  if r <= 3 then return pick(PATTERNS) end
+  if r == 4 then return pick(PREDICATES) end
+  return gen.array(0, function() return pick(PATTERNS) end)
+end
+
+function gen.spec(names)
+  local r = random(3)
+  if r == 1 then return pick(WORDS) end
+  if r == 2 then
+    return named("actionIsPing", function(m) return m.Action == "Ping" end)
+  end
+  local spec = {}
+  for _ = 1, random(3) do
+    -- Mostly keys the message has, sometimes ones it lacks
+    local key = random(3) > 1 and pick(names) or pick(NAMES)
+    spec[key] = gen.pattern()
+  end
+  return spec
+end
+
+-- The Rust ao fixes the order of custom tags (see tests/ao.rs) while the reference
+-- follows `pairs`, so ao results compare their tags as sets
+local function unordered(tags)
+  local function key(tag) return tostring(tag.name) .. "\0" .. tostring(tag.value) end
+  table.sort(tags, function(a, b) return key(a) < key(b) end)
+  return tags
+end
+
+local GROUPS = {
+  ["stringify.isSimpleArray"] = {
+    args = function() return gen.table(2) end,
+    run = function(m, t) return m.stringify.isSimpleArray(t) end,
+  },
+  ["stringify.format"] = {
+    args = function() return gen.table(3) end,
+    run = function(m, t) return m.stringify.format(t) end,
+  },
+  ["utils.isArray"] = {
+    args = function() return gen.table(1) end,
+    run = function(m, t) return m.utils.isArray(t) end,
+  },
+  ["utils.matchesPattern"] = {
+    args = function() return gen.pattern(), pick(WORDS), (gen.message()) end,
+    run = function(m, pattern, value, msg) return m.utils.matchesPattern(pattern, value, msg) end,
+  },
+  ["utils.matchesSpec"] = {
+    args = function()
+      local msg, names = gen.message()
+      return msg, gen.spec(names)
+    end,
+    run = function(m, msg, spec) return m.utils.matchesSpec(msg, spec) end,
+  },
+  ["utils.concat"] = {
+    args = function() return gen.array(1), random(4) == 1 and gen.map(0) or gen.array(1) end,
+    run = function(m, a, b) return m.utils.concat(a)(b) end,
+  },
+  ["utils.reduce"] = {
+    args = function()
+      local numbers = gen.array(0, function() return random(100) - 50 end)
+      if random(2) == 1 then
+        return named("sum", function(acc, v) return acc + v end), 0, numbers
+      end
+      return named("join", function(acc, v, k) return acc .. k .. "=" .. tostring(v) .. ";" end), "", gen.table(0)
+    end,
+    run = function(m, fn, initial, t) return m.utils.reduce(fn)(initial)(t) end,
+  },
+  ["utils.map"] = {
+    args = function() return pick(UNARY), gen.array(0) end,
+    run = function(m, fn, t) return m.utils.map(fn)(t) end,
+  },
+  ["utils.filter"] = {
+    args = function() return pick(PREDICATES), gen.array(0) end,
+    run = function(m, fn, t) return m.utils.filter(fn)(t) end,
+  },
+  ["utils.find"] = {
+    args = function() return pick(PREDICATES), gen.array(0) end,
+    run = function(m, fn, t) return m.utils.find(fn)(t) end,
+  },
+  ["utils.propEq"] = {
+    args = function() return pick(NAMES), pick(WORDS), (gen.message()) end,
+    run = function(m, name, value, obj) return m.utils.propEq(name)(value)(obj) end,
+  },
+  ["utils.reverse"] = {
+    args = function() return gen.table(1) end,
+    run = function(m, t) return m.utils.reverse(t) end,
+  },
+  ["utils.compose"] = {
+    args = function()
+      local fns = { pick(UNARY), pick(UNARY) }
+      if random(2) == 1 then fns[3] = pick(UNARY) end
+      return fns, gen.scalar()
+    end,
+    run = function(m, fns, v) return m.utils.compose(table.unpack(fns))(v) end,
+  },
+  ["utils.prop"] = {
+    args = function() return pick(NAMES), (gen.message()) end,
+    run = function(m, name, obj) return m.utils.prop(name)(obj) end,
+  },
+  ["utils.includes"] = {
+    args = function() return pick(WORDS), gen.strings() end,
+    run = function(m, value, t) return m.utils.includes(value)(t) end,
+  },
+  ["utils.keys"] = {
+    args = function() return gen.table(1) end,
+    run = function(m, t) return m.utils.keys(t) end,
+  },
+  ["utils.values"] = {
+    args = function() return gen.table(1) end,
+    run = function(m, t) return m.utils.values(t) end,
+  },
+  ["ao.send"] = {
+    args = function() return gen.outgoing() end,
+    run = function(m, msg)
+      m.reset()
+      local message = m.ao.send(msg)
+      unordered(message.Tags)
+      return { message = message, outbox = m.ao.outbox }
+    end,
+  },
+  ["ao.spawn"] = {
+    args = function() return pick(WORDS), gen.outgoing() end,
+    run = function(m, module, msg)
+      m.reset()
+      local spawn = m.ao.spawn(module, msg)
+      unordered(spawn.Tags)
+      return { spawn = spawn, outbox = m.ao.outbox }
+    end,
+  },
+  ["ao.normalize"] = {
+    args = function()
+      local msg = gen.message()
+      msg.Tags = gen.array(0, function() return { name = pick(NAMES), value = pick(WORDS) } end)
+      return msg
+    end,
+    run = function(m, msg) return m.ao.normalize(msg) end,
+  },
+  ["ao.sanitize"] = {
+    args = function()
+      local msg = gen.map(1)
+      msg.Tags = gen.map(0)
+      return msg
+    end,
+    run = function(m, msg) return m.ao.sanitize(msg) end,
+  },
+  ["handlersUtils.hasMatchingTag"] = {
+    args = function() return pick(NAMES), pick(WORDS), (gen.message()) end,
+    run = function(m, name, value, msg) return m.handlersUtils.hasMatchingTag(name, value)(msg) end,
+  },
+  ["handlersUtils.hasMatchingTagOf"] = {
+    args = function() return pick(NAMES), gen.strings(), (gen.message()) end,
+    run = function(m, name, values, msg) return m.handlersUtils.hasMatchingTagOf(name, values)(msg) end,
+  },
+  ["handlersUtils.hasMatchingData"] = {
+    args = function() return pick(WORDS), (gen.message()) end,
+    run = function(m, value, msg) return m.handlersUtils.hasMatchingData(value)(msg) end,
+  },
+  ["handlersUtils.reply"] = {
+    args = function()
+      local input = pick(WORDS)
+      if random(2) == 1 then input = { Action = pick(WORDS), Data = input } end
+      return input, (gen.message())
+    end,
+    run = function(m, input, msg)
+      local replies = {}
+      msg.reply = function(...) replies[#replies + 1] = table.pack(...) end
+      m.handlersUtils.reply(input)(msg)
+      return replies
+    end,
+  },
+  ["handlersUtils.continue"] = {
+    args = function()
+      return pick({
+        named("always", function() return true end),
+        named("never", function() return false end),
+        named("zero", function() return 0 end),
+        named("minusOne", function() return -1 end),
+        named("skip", function() return "skip" end),
+        named("none", function() return nil end),
+        named("isPing", function(msg) return msg.Action == "Ping" end),
+      }), (gen.message())
+    end,
+    run = function(m, fn, msg) return m.handlersUtils.continue(fn)(msg) end,
+  },
+}
+
+-- Copies arguments so neither side sees the other's mutations. Both sides get a
+-- copy built the same way, so `pairs` visits their keys in the same order.
+local function copy(value)
+  if type(value) ~= "table" then return value end
+  local result = {}
+  for k, v in pairs(value) do result[k] = copy(v) end
+  return result
+end
+
+-- Numbers compare by value (Rust may return 1.0 where Lua returns 1), functions
+-- compare equal to any function
+local function same(a, b)
+  if type(a) ~= type(b) then return false end
+  if type(a) == "function" then return true end
+  if type(a) ~= "table" then return a == b end
+  for k, v in pairs(a) do
+    if not same(v, b[k]) then return false end
+  end
+  for k in pairs(b) do
+    if a[k] == nil then return false end
+  end
+  return true
+end
+
+local function describe(value, depth)
+  depth = depth or 0
+  local kind = type(value)
+  if kind == "string" then return (string.format("%q", value):gsub("\\\n", "\\n")) end
+  if kind == "function" then return NAMED[value] or "<function>" end
+  if kind ~= "table" then return tostring(value) end
+  if depth > 4 then return "{...}" end
+  local keys = {}
+  for k in pairs(value) do keys[#keys + 1] = k end
+  table.sort(keys, function(x, y)
+    if type(x) ~= type(y) then return type(x) < type(y) end
+    return x < y
+  end)
+  local parts = {}
+  for _, k in ipairs(keys) do
+    parts[#parts + 1] = "[" .. describe(k) .. "] = " .. describe(value[k], depth + 1)
+  end
+  return "{ " .. table.concat(parts, ", ") .. " }"
+end
+
+local function outcome(ok, result)
+  if ok then return describe(result) end
+  return "error: " .. tostring(result)
+end
+
+return function(sources, name)
+  local group = assert(GROUPS[name], "unknown group " .. name)
+  local reference = referenceLoader(sources)
+
+  Handlers = require(".handlers")
+  -- The reference modules send anything, so only compare what gets sent
+  ao.validation = "off"
+  local refAo = reference(".ao")
+  refAo.init(ENV)
+
+  local impls = {
+    rust = {
+      ao = ao,
+      utils = require(".utils"),
+      stringify = require(".stringify"),
+      handlersUtils = require(".handlers-utils"),
+      reset = function()
+        ao.reference = 0
+        ao.clearOutbox()
+      end,
+    },
+    reference = {
+      ao = refAo,
+      utils = reference(".utils"),
+      stringify = reference(".stringify"),
+      handlersUtils = reference(".handlers-utils"),
+      reset = function()
+        refAo.reference = 0
+        refAo.clearOutbox()
+      end,
+    },
+  }
+
+  local divergences = {}
+  for case = 1, CASES do
+    local args = table.pack(group.args())
+    local rustOk, rustResult = pcall(group.run, impls.rust, table.unpack(copy(args), 1, args.n))
+    local refOk, refResult = pcall(group.run, impls.reference, table.unpack(copy(args), 1, args.n))
+    -- Errors only need to agree on the fact that something failed
+    if rustOk ~= refOk or (rustOk and not same(rustResult, refResult)) then
+      local shown = {}
+      for i = 1, args.n do shown[i] = describe(args[i]) end
+      divergences[#divergences + 1] = string.format(
+        "case %d: %s(%s)\n  rust:      %s\n  reference: %s",
+        case, name, table.concat(shown, ", "),
+        outcome(rustOk, rustResult), outcome(refOk, refResult))
+    end
+  end
+  return divergences
+end
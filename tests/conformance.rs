@@ -0,0 +1,114 @@
+//! Differential conformance suite: feeds generated tables and messages to the Rust
+//! modules and to the reference aos Lua modules in `tests/reference/`, and fails on
+//! any case where they disagree. The case generators live in `conformance.lua`.
+//!
+//! Run with `cargo test --no-default-features --features host --test conformance`.
+mod common;
+
+use mlua::prelude::*;
+
+const DRIVER: &str = include_str!("conformance.lua");
+
+const REFERENCE: &[(&str, &str)] = &[
+    (".ao", include_str!("reference/ao.lua")),
+    (
+        ".handlers-utils",
+        include_str!("reference/handlers-utils.lua"),
+    ),
+    (".stringify", include_str!("reference/stringify.lua")),
+    (".utils", include_str!("reference/utils.lua")),
+];
+
+/// Divergences kept on purpose. Their reports are printed but don't fail the suite.
+const KNOWN: &[(&str, &str)] = &[
+    (
+        "handlersUtils.hasMatchingTag",
+        "returns the handler codes -1/0 instead of true/false; Handlers treats them alike",
+    ),
+    (
+        "handlersUtils.hasMatchingData",
+        "returns the handler codes -1/0 instead of true/false; Handlers treats them alike",
+    ),
+];
+
+/// How many reports of one group to show before eliding the rest.
+const SHOWN: usize = 5;
+
+fn check(groups: &[&str]) {
+    let mut failures = Vec::new();
+    for group in groups {
+        let lua = common::lua();
+        let sources = lua.create_table().unwrap();
+        for (name, source) in REFERENCE {
+            sources.set(*name, *source).unwrap();
+        }
+        let run: LuaFunction = lua
+            .load(DRIVER)
+            .set_name("=conformance")
+            .eval()
+            .expect("load conformance driver");
+        let divergences: Vec<String> = run
+            .call((sources, *group))
+            .unwrap_or_else(|e| panic!("{}: {}", group, e));
+        if divergences.is_empty() {
+            continue;
+        }
+
+        let mut report = format!("{}: {} divergent cases", group, divergences.len());
+        for divergence in divergences.iter().take(SHOWN) {
+            report.push('\n');
+            report.push_str(divergence);
+        }
+        match KNOWN.iter().find(|(name, _)| name == group) {
+            Some((_, reason)) => println!("known divergence ({})\n{}", reason, report),
+            None => failures.push(report),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "Rust modules diverge from the reference:\n{}",
+        failures.join("\n\n")
+    );
+}
+
+#[test]
+fn stringify_matches_reference() {
+    check(&["stringify.isSimpleArray", "stringify.format"]);
+}
+
+#[test]
+fn utils_matches_reference() {
+    check(&[
+        "utils.isArray",
+        "utils.matchesPattern",
+        "utils.matchesSpec",
+        "utils.concat",
+        "utils.reduce",
+        "utils.map",
+        "utils.filter",
+        "utils.find",
+        "utils.propEq",
+        "utils.reverse",
+        "utils.compose",
+        "utils.prop",
+        "utils.includes",
+        "utils.keys",
+        "utils.values",
+    ]);
+}
+
+#[test]
+fn ao_matches_reference() {
+    check(&["ao.send", "ao.spawn", "ao.normalize", "ao.sanitize"]);
+}
+
+#[test]
+fn handlers_utils_matches_reference() {
+    check(&[
+        "handlersUtils.hasMatchingTag",
+        "handlersUtils.hasMatchingTagOf",
+        "handlersUtils.hasMatchingData",
+        "handlersUtils.reply",
+        "handlersUtils.continue",
+    ]);
+}
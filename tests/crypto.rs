@@ -0,0 +1,182 @@
+mod common;
+
+use common::{lua, render};
+
+const SETUP: &str = r#"
+crypto = require(".crypto")
+function failure(f, ...)
+  local ok, err = pcall(f, ...)
+  assert(not ok, "expected an error")
+  return tostring(err)
+end
+"#;
+
+#[test]
+fn sha2_256_vectors() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    // FIPS 180-4 examples
+    let results = render(
+        &lua,
+        r#"local out = {}
+        for _, input in ipairs({
+          "",
+          "abc",
+          "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        }) do
+          out[#out + 1] = crypto.digest.sha2_256(input).asHex()
+        end
+        return out"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \
+         \"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\",\n  \
+         \"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\",\n  \
+         \"248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1\",\n}\n"
+    );
+}
+
+#[test]
+fn sha3_vectors() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    // FIPS 202 examples
+    let results = render(
+        &lua,
+        r#"return {
+          crypto.digest.sha3_256("").asHex(),
+          crypto.digest.sha3_256("abc").asHex(),
+          crypto.digest.sha3_512("").asHex(),
+          crypto.digest.sha3_512("abc").asHex(),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \
+         \"a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a\",\n  \
+         \"3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532\",\n  \
+         \"a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a6\
+         15b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26\",\n  \
+         \"b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+         10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0\",\n}\n"
+    );
+}
+
+#[test]
+fn keccak256_uses_the_original_padding() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    // Keccak team vectors, as used by Ethereum; they differ from SHA3-256
+    let results = render(
+        &lua,
+        r#"return {
+          crypto.digest.keccak256("").asHex(),
+          crypto.digest.keccak256("abc").asHex(),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \
+         \"c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470\",\n  \
+         \"4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45\",\n}\n"
+    );
+}
+
+#[test]
+fn blake2b_vectors() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    // RFC 7693 Appendix A, plus the reference implementation's keyed KAT
+    let results = render(
+        &lua,
+        r#"local key = {}
+        for i = 0, 63 do key[#key + 1] = i end
+        return {
+          crypto.digest.blake2b("abc").asHex(),
+          crypto.digest.blake2b("abc", 32).asHex(),
+          crypto.digest.blake2b("", 64, key).asHex(),
+          #crypto.digest.blake2b("abc", 20).asString(),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \
+         \"ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+         7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923\",\n  \
+         \"bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319\",\n  \
+         \"10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786\
+         b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568\",\n  \
+         20,\n}\n"
+    );
+}
+
+#[test]
+fn hmac_sha256_vectors() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    // RFC 4231 test cases 1, 2, 5 (truncated to 128 bits) and 6
+    let results = render(
+        &lua,
+        r#"return {
+          crypto.digest.hmac("Hi There", string.rep("\11", 20)).asHex(),
+          crypto.digest.hmac("what do ya want for nothing?", "Jefe", "sha256").asHex(),
+          crypto.mac.createHmac("Test With Truncation", string.rep("\12", 20)).asHex():sub(1, 32),
+          crypto.mac.createHmac(
+            "Test Using Larger Than Block-Size Key - Hash Key First",
+            string.rep("\170", 131),
+            "sha2_256"
+          ).asHex(),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \
+         \"b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7\",\n  \
+         \"5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843\",\n  \
+         \"a3b6167473100ee06e0c796c2955552b\",\n  \
+         \"60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54\",\n}\n"
+    );
+}
+
+#[test]
+fn results_and_inputs() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local digest = crypto.digest.sha2_256("abc")
+        local bytes = digest.asBytes()
+        return {
+          #bytes,
+          bytes[1],
+          bytes[32],
+          crypto.utils.hex.stringToHex(digest.asString()) == digest.asHex(),
+          crypto.digest.sha2_256({ 97, 98, 99 }).asHex() == digest.asHex(),
+          crypto.digest.sha2_256(crypto.utils.stream.fromHex("616263")).asHex() == digest.asHex(),
+          failure(crypto.digest.sha2_256, { 256 }),
+          failure(crypto.digest.hmac, "data", "key", "md5"),
+          failure(crypto.digest.blake2b, "abc", 65),
+        }"#,
+    );
+    assert!(
+        results.starts_with("return {\n  32,\n  186,\n  173,\n  true,\n  true,\n  true,\n"),
+        "{}",
+        results
+    );
+    assert!(
+        results.contains("byte value 256 is out of range"),
+        "{}",
+        results
+    );
+    assert!(
+        results.contains("unsupported hash algorithm 'md5'"),
+        "{}",
+        results
+    );
+    assert!(
+        results.contains("blake2b output length must be between 1 and 64 bytes"),
+        "{}",
+        results
+    );
+}
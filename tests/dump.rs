@@ -0,0 +1,219 @@
+mod common;
+
+use common::lua;
+
+const SETUP: &str = r#"
+dump = require(".dump")
+function failure(f, ...)
+  local ok, err = pcall(f, ...)
+  assert(not ok, "expected an error")
+  return tostring(err)
+end
+-- Structural equality that follows cycles by pairing up the tables already compared,
+-- telling integers from floats and 0.0 from -0.0
+function same(a, b, paired)
+  if type(a) ~= "table" or type(b) ~= "table" then
+    if a ~= a then return b ~= b end
+    return a == b and math.type(a) == math.type(b) and (a ~= 0 or 1 / a == 1 / b)
+  end
+  paired = paired or {}
+  if paired[a] ~= nil then return paired[a] == b end
+  paired[a] = b
+  for k, v in pairs(a) do
+    if not same(v, b[k], paired) then return false end
+  end
+  for k in pairs(b) do
+    if a[k] == nil then return false end
+  end
+  return true
+end
+"#;
+
+#[test]
+fn shared_tables_keep_their_identity() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let (source, identical, first): (String, bool, i64) = lua
+        .load(
+            r#"local shared = { 1 }
+            local source = dump.dump({ a = shared, b = shared })
+            local loaded = dump.load(source)
+            return source, loaded.a == loaded.b, loaded.a[1]"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(
+        source,
+        "local r = {}\nr[1] = {}\nr[1][1] = 1\nreturn {\n  a = r[1],\n  b = r[1],\n}\n"
+    );
+    assert!(identical);
+    assert_eq!(first, 1);
+}
+
+#[test]
+fn cycles_load_back_into_the_same_shape() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let (source, resaved, cyclic, equal): (String, String, bool, bool) = lua
+        .load(
+            r#"local root = { name = "root", list = { 1, 2 } }
+            root.self = root
+            root.child = { parent = root }
+            local source = dump.dump(root)
+            local loaded = dump.load(source)
+            return
+              source,
+              dump.dump(loaded),
+              loaded.self == loaded and loaded.child.parent == loaded,
+              same(root, loaded)"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(
+        source,
+        "local r = {}\n\
+         r[1] = {}\n\
+         r[1].child = {\n  parent = r[1],\n}\n\
+         r[1].list = {\n  1,\n  2,\n}\n\
+         r[1].name = \"root\"\n\
+         r[1].self = r[1]\n\
+         return r[1]\n"
+    );
+    assert_eq!(resaved, source);
+    assert!(cyclic);
+    assert!(equal);
+}
+
+#[test]
+fn options_change_the_layout() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let (default, compact, wide, unsorted, shared): (String, String, String, String, String) = lua
+        .load(
+            r#"local value = { 1, 2, key = { nested = true } }
+            local shared = {}
+            return
+              dump.dump(value),
+              dump.dump(value, { compact = true }),
+              dump.dump(value, { indent = 4 }),
+              dump.dump({ 3, 1, 2, z = 1 }, { sorted = false }),
+              dump.dump({ shared, shared }, { compact = true })"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(
+        default,
+        "return {\n  1,\n  2,\n  key = {\n    nested = true,\n  },\n}\n"
+    );
+    assert_eq!(compact, "return {1,2,key={nested=true}}");
+    assert_eq!(
+        wide,
+        "return {\n    1,\n    2,\n    key = {\n        nested = true,\n    },\n}\n"
+    );
+    // The sequence stays positional whether or not the keys are sorted
+    assert_eq!(unsorted, "return {\n  3,\n  1,\n  2,\n  z = 1,\n}\n");
+    assert_eq!(shared, "local r={};r[1]={};return {r[1],r[1]}");
+}
+
+#[test]
+fn keys_sort_by_type_then_value() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let source: String = lua
+        .load(
+            r#"return dump.dump({
+              ["with space"] = 7, ["not"] = 6, b = 4, a = 5,
+              [10] = 3, [2.5] = 2, [true] = 1,
+            })"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(
+        source,
+        "return {\n  [true] = 1,\n  [2.5] = 2,\n  [10] = 3,\n  a = 5,\n  b = 4,\n  \
+         [\"not\"] = 6,\n  [\"with space\"] = 7,\n}\n"
+    );
+}
+
+#[test]
+fn strings_escape_control_and_invalid_bytes() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let (source, equal): (String, bool) = lua
+        .load(
+            r#"local value = { "a\0b\1\127\r\t", "é", "\255a\"\n\t\r" }
+            local source = dump.dump(value)
+            return source, same(value, dump.load(source))"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(
+        source,
+        r#"return {
+  "a\000b\001\127\r\t",
+  "é",
+  "\255a\"\n\009\013",
+}
+"#
+    );
+    assert!(equal);
+}
+
+#[test]
+fn special_numbers_round_trip() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let (source, checks): (String, Vec<bool>) = lua
+        .load(
+            r#"local value = {
+              0 / 0, math.huge, -math.huge, -0.0, 0.1, 1e100, 3.0,
+              math.mininteger, math.maxinteger,
+            }
+            local source = dump.dump(value)
+            local loaded = dump.load(source)
+            return source, {
+              same(value, loaded),
+              loaded[1] ~= loaded[1],
+              1 / loaded[4] == -math.huge,
+              math.type(loaded[7]) == "float",
+              math.type(loaded[8]) == "integer",
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(
+        source,
+        "return {\n  0/0,\n  math.huge,\n  -math.huge,\n  -0.0,\n  0.1,\n  1e100,\n  3.0,\n  \
+         math.mininteger,\n  9223372036854775807,\n}\n"
+    );
+    assert_eq!(checks, [true; 5]);
+}
+
+#[test]
+fn load_is_isolated_and_dump_rejects_code() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results: Vec<String> = lua
+        .load(
+            r#"Secret = "global"
+            return {
+              tostring(dump.load("return Secret")),
+              tostring(dump.load("return math.pi") == math.pi),
+              failure(dump.dump, { print }),
+              failure(dump.load, "\27Lua"),
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(results[..2], ["nil", "true"]);
+    assert!(
+        results[2].contains("cannot dump a value of type 'function'"),
+        "{}",
+        results[2]
+    );
+    assert!(
+        results[3].contains("attempt to load a binary chunk"),
+        "{}",
+        results[3]
+    );
+}
@@ -0,0 +1,354 @@
+mod common;
+
+use common::{assert_golden, lua, render};
+
+const SETUP: &str = r#"
+Prompt = function() return "aos> " end
+EVAL = require(".eval")(ao)
+"#;
+
+#[test]
+fn eval_expression_sets_output() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let output = render(
+        &lua,
+        r#"EVAL({ Data = "1 + 1" })
+        return ao.outbox.Output"#,
+    );
+    assert_golden("eval_expression", &output);
+}
+
+#[test]
+fn eval_table_is_formatted() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let output = render(
+        &lua,
+        r#"EVAL({ Data = "{ 1, 2 }" })
+        return ao.outbox.Output"#,
+    );
+    assert_golden("eval_table", &output);
+}
+
+#[test]
+fn eval_statement_runs_without_output() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let output = render(
+        &lua,
+        r#"EVAL({ Data = "x = 5" })
+        return { x = x, Output = ao.outbox.Output }"#,
+    );
+    assert_golden("eval_statement", &output);
+}
+
+#[test]
+fn eval_error_sets_outbox_error() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let error = render(
+        &lua,
+        r#"debug = { traceback = function(co) return "traceback of a " .. coroutine.status(co) end }
+        EVAL({ Id = "MSG", Data = "local x = 1\nerror('boom')" })
+        assert(ao.outbox.Error == Errors[#Errors].message)
+        return Errors[#Errors]"#,
+    );
+    assert_eq!(
+        error,
+        "return {\n  chunk = \"[string \\\"aos\\\"]\",\n  kind = \"runtime\",\n  line = 2,\n  \
+         message = \"[string \\\"aos\\\"]:2: boom\",\n  messageId = \"MSG\",\n  \
+         traceback = \"traceback of a dead\",\n}\n"
+    );
+}
+
+#[test]
+fn eval_errors_are_kept_in_a_ring() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let errors = render(
+        &lua,
+        r#"MAX_ERRORS = 2
+        EVAL({ Id = "1", Data = "error('one')" })
+        EVAL({ Id = "2", Data = "x =" })
+        EVAL({ Id = "3", Data = "ao.send('not a message')" })
+        local kinds = {}
+        for i, error in ipairs(Errors) do kinds[i] = error.messageId .. " " .. error.kind end
+        return { #Errors, kinds, Errors[2].message == ao.outbox.Error }"#,
+    );
+    assert_eq!(
+        errors,
+        "return {\n  2,\n  {\n    \"2 syntax\",\n    \"3 callback\",\n  },\n  true,\n}\n"
+    );
+}
+
+#[test]
+fn negative_max_errors_keeps_none() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"MAX_ERRORS = -1
+        local ok = pcall(EVAL, { Id = "MSG", Data = "error('boom', 0)" })
+        return { ok, ao.outbox.Error, #Errors }"#,
+    );
+    assert_eq!(results, "return {\n  true,\n  \"boom\",\n  0,\n}\n");
+}
+
+#[test]
+fn eval_stops_at_instruction_limit() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let error = render(
+        &lua,
+        r#"ao.limits = { instructions = 10000 }
+        EVAL({ Data = "while true do pcall(function() while true do end end) end" })
+        local error = Errors[#Errors]
+        assert(ao.outbox.Error == "instructions limit exceeded")
+        assert(error.used > error.max)
+        ao.outbox.Error = nil
+        EVAL({ Data = "1 + 1" })
+        return { error.kind, error.limit, error.max, ao.outbox.Output.data.output }"#,
+    );
+    assert_eq!(
+        error,
+        "return {\n  \"limit\",\n  \"instructions\",\n  10000,\n  2,\n}\n"
+    );
+}
+
+#[test]
+fn eval_stops_at_memory_limit() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let error = render(
+        &lua,
+        r#"ao.limits = { memory = 1 << 20 }
+        EVAL({ Data = "local t = {} for i = 1, 1e7 do t[i] = tostring(i) end" })
+        local error = Errors[#Errors]
+        return { error.kind, error.limit, error.max }"#,
+    );
+    assert_eq!(
+        error,
+        "return {\n  \"limit\",\n  \"memory\",\n  1048576,\n}\n"
+    );
+}
+
+#[test]
+fn eval_failing_before_it_runs_removes_the_meter() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"ao.limits = { instructions = 1000 }
+        -- Reading `print` fails, so the eval errors after the meter has started
+        local realPrint = print
+        print = nil
+        setmetatable(_G, { __index = function(_, key)
+          if key == "print" then error("print is gone") end
+        end })
+        local ok = pcall(EVAL, { Data = "1 + 1" })
+        setmetatable(_G, nil)
+        print = realPrint
+        local n = 0
+        for i = 1, 100000 do n = n + i end
+        return { ok, n }"#,
+    );
+    assert_eq!(results, "return {\n  false,\n  5000050000,\n}\n");
+}
+
+#[test]
+fn eval_without_limits_runs_to_completion() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let output = render(
+        &lua,
+        r#"EVAL({ Data = "local n = 0 for i = 1, 100000 do n = n + i end return n" })
+        return { ao.outbox.Error == nil, ao.outbox.Output.data.output }"#,
+    );
+    assert_eq!(output, "return {\n  true,\n  5000050000,\n}\n");
+}
+
+#[test]
+fn sandbox_tag_restricts_globals() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r##"Counter, Secret = 0, "secret"
+        ao.authorities = { "A", "B" }
+        ao.sandbox = { writable = { "Counter" } }
+        local function eval(code, sandbox)
+          ao.outbox.Error = nil
+          local tags = sandbox and { { name = "Sandbox", value = "true" } } or {}
+          EVAL({ Data = code, Tags = tags })
+          return tostring(ao.outbox.Error or ao.outbox.Output.data.output)
+        end
+        local function refused(code)
+          return eval(code, true):find("is read-only in the sandbox", 1, true) ~= nil
+        end
+        return {
+          eval("Secret == nil and io == nil and Handlers == nil and ao == ao", true),
+          eval("local n = #ao.authorities for _, a in pairs(ao.authorities) do n = n + #a end return n", true),
+          refused("ao.outbox = {}"),
+          refused("string.rep = nil"),
+          eval("Counter = Counter + 1; Leaked = true; return Leaked", true),
+          Counter,
+          Leaked == nil,
+          eval("Secret"),
+        }"##,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"true\",\n  \"4\",\n  true,\n  true,\n  \"true\",\n  1,\n  true,\n  \
+         \"secret\",\n}\n"
+    );
+}
+
+#[test]
+fn malformed_tags_still_reach_the_error_report() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local tags = { { name = "Sandbox" }, "junk" }
+        EVAL({ Id = "BAD", From = {}, Data = "error('boom')", Tags = tags })
+        local error = Errors[#Errors]
+        EVAL({ Id = "OK", Data = "io ~= nil", Tags = tags })
+        return { error.messageId, error.kind, ao.outbox.Output.data.output }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"BAD\",\n  \"runtime\",\n  true,\n}\n"
+    );
+}
+
+#[test]
+fn eval_waiting_for_a_reply_resumes_with_it() {
+    let lua = lua();
+    let results = render(
+        &lua,
+        r#"Handlers = require(".handlers")
+        local process = require(".process")
+        local target = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw"
+        local function message(fields)
+          fields.Target = fields.Target or "PROCESS"
+          fields.Owner = fields.From
+          return fields
+        end
+        local asked = process.handle(message({
+          Id = "EVAL", From = "OWNER", Tags = { { name = "Action", value = "Eval" } },
+          Data = "local reply = ao.send({ Target = '" .. target .. "', Action = 'Ping' }).receive()"
+            .. " return 'got ' .. reply.Data",
+        }), ENV)
+        process.handle(message({
+          Id = "OTHER", From = target, Data = "other",
+          Tags = { { name = "X-Reference", value = "7" } },
+        }), ENV)
+        local answered = process.handle(message({
+          Id = "REPLY", From = target, Data = "pong",
+          Tags = { { name = "X-Reference", value = "1" } },
+        }), ENV)
+        return {
+          #asked.Messages,
+          asked.Output.data == nil,
+          answered.Output.data.output,
+          #Handlers.list,
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  1,\n  true,\n  \"got pong\",\n  2,\n}\n"
+    );
+}
+
+#[test]
+fn parked_evals_report_under_their_own_message_and_are_capped() {
+    let lua = lua();
+    let results = render(
+        &lua,
+        r#"Handlers = require(".handlers")
+        local process = require(".process")
+        local target = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw"
+        local function message(fields)
+          fields.Target = "PROCESS"
+          fields.Owner = fields.From
+          return process.handle(fields, ENV)
+        end
+        local function eval(id, reference, code)
+          return message({
+            Id = id, From = "OWNER", Tags = { { name = "Action", value = "Eval" } },
+            Data = "local reply = Handlers.receive({ From = '" .. target
+              .. "', ['X-Reference'] = '" .. reference .. "' }) " .. code,
+          })
+        end
+        local function reply(reference, data)
+          return message({
+            Id = "REPLY", From = target, Data = data,
+            Tags = { { name = "X-Reference", value = reference } },
+          })
+        end
+        local function last() return Errors[#Errors] end
+        Seen = 0
+        Handlers.add("seen", { From = target }, function() Seen = Seen + 1 end)
+
+        eval("FIRST", "1", "error('bad ' .. reply.Data)")
+        eval("SECOND", "1", "return 1")
+        local duplicate = last()
+        reply("1", "pong")
+        local resumed = last()
+        local seenConsumed = Seen
+        reply("1", "again")
+        local seenUnmatched = Seen
+
+        local newest
+        for i = 1, 101 do newest = eval("E" .. i, "r" .. i, "return reply.Data") end
+        local evicted = last()
+        reply("r1", "late")
+        local kept = reply("r2", "on time")
+        return {
+          duplicate.messageId, duplicate.kind,
+          resumed.messageId, resumed.message:find("bad pong", 1, true) ~= nil,
+          seenConsumed, seenUnmatched,
+          evicted.messageId, evicted.kind, newest.Error == nil,
+          Seen, kept.Output.data.output,
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"SECOND\",\n  \"dropped\",\n  \"FIRST\",\n  true,\n  0,\n  1,\n  \
+         \"E1\",\n  \"dropped\",\n  true,\n  2,\n  \"on time\",\n}\n"
+    );
+}
+
+#[test]
+fn eval_returns_every_value_after_printed_lines() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local before = print
+        local function eval(code)
+          EVAL({ Data = code })
+          return ao.outbox.Output
+        end
+        local several = eval("return 1, 'a', { 2 }")
+        local holes = eval("return 1, nil, 3, nil")
+        local printed = eval("print('hi', { 3 }) print(nil) return 4")
+        local only = eval("print('just this')")
+        return {
+          plain(several.data.output),
+          several.json,
+          plain(holes.data.output),
+          holes.json,
+          plain(printed.data.output),
+          only.data.output,
+          print == before,
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"1\\ta\\t{ 2 }\",\n  \"[1,\\\"a\\\",[2]]\",\n  \
+         \"1\\tnil\\t3\\tnil\",\n  \"[1,null,3,null]\",\n  \"hi\\t{ 3 }\\nnil\\n4\",\n  \
+         \"just this\",\n  true,\n}\n"
+    );
+}
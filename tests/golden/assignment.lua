@@ -0,0 +1,14 @@
+return {
+  afterRemove = 1,
+  count = 2,
+  isAssignable = {
+    true,
+    true,
+    false,
+  },
+  isAssignment = {
+    false,
+    true,
+  },
+  name = "credit",
+}
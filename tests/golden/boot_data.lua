@@ -0,0 +1,4 @@
+return {
+  booted = true,
+  inbox = 1,
+}
@@ -0,0 +1,4 @@
+return {
+  bootedFrom = "weavedrive",
+  inbox = 1,
+}
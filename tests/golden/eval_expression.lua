@@ -0,0 +1,8 @@
+return {
+  data = {
+    output = 2,
+    prompt = "aos> ",
+  },
+  json = "2",
+  prompt = "aos> ",
+}
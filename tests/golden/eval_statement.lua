@@ -0,0 +1,10 @@
+return {
+  Output = {
+    data = {
+      prompt = "aos> ",
+    },
+    json = "null",
+    prompt = "aos> ",
+  },
+  x = 5,
+}
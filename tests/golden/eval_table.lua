@@ -0,0 +1,8 @@
+return {
+  data = {
+    output = "{ \027[34m1\027[0m, \027[34m2\027[0m }",
+    prompt = "aos> ",
+  },
+  json = "[1,2]",
+  prompt = "aos> ",
+}
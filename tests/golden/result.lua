@@ -0,0 +1,37 @@
+return {
+  Assignments = {
+    {
+      Message = "M1",
+      Processes = {
+        "P1",
+      },
+    },
+  },
+  Messages = {
+    {
+      Anchor = "00000000000000000000000000000001",
+      Data = "hello",
+      Tags = {
+        {
+          name = "Data-Protocol",
+          value = "ao",
+        },
+        {
+          name = "Variant",
+          value = "ao.TN.1",
+        },
+        {
+          name = "Type",
+          value = "Message",
+        },
+        {
+          name = "Reference",
+          value = "1",
+        },
+      },
+      Target = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw",
+    },
+  },
+  Output = {},
+  Spawns = {},
+}
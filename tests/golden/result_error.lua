@@ -0,0 +1,8 @@
+return {
+  {
+    Error = "boom",
+  },
+  {
+    Error = "outbox boom",
+  },
+}
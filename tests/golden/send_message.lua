@@ -0,0 +1,27 @@
+return {
+  Anchor = "00000000000000000000000000000001",
+  Data = "hello",
+  Tags = {
+    {
+      name = "Data-Protocol",
+      value = "ao",
+    },
+    {
+      name = "Variant",
+      value = "ao.TN.1",
+    },
+    {
+      name = "Type",
+      value = "Message",
+    },
+    {
+      name = "Reference",
+      value = "1",
+    },
+    {
+      name = "Action",
+      value = "Ping",
+    },
+  },
+  Target = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw",
+}
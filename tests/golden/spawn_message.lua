@@ -0,0 +1,38 @@
+return {
+  Anchor = "00000000000000000000000000000001",
+  Data = "init",
+  Tags = {
+    {
+      name = "Data-Protocol",
+      value = "ao",
+    },
+    {
+      name = "Variant",
+      value = "ao.TN.1",
+    },
+    {
+      name = "Type",
+      value = "Process",
+    },
+    {
+      name = "From-Process",
+      value = "PROCESS",
+    },
+    {
+      name = "From-Module",
+      value = "MODULE",
+    },
+    {
+      name = "Module",
+      value = "CHILD-MODULE",
+    },
+    {
+      name = "Reference",
+      value = "1",
+    },
+    {
+      name = "Name",
+      value = "child",
+    },
+  },
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use common::{lua, render};
+
+const SETUP: &str = r#"
+Handlers = require(".handlers")
+Ran = {}
+function names()
+  local names = {}
+  for _, o in ipairs(Handlers.list) do names[#names + 1] = o.name end
+  return table.concat(names, ",")
+end
+function record(name)
+  return function(msg) Ran[#Ran + 1] = name .. ":" .. tostring(msg.Action) end
+end
+"#;
+
+#[test]
+fn add_append_and_prepend_place_handlers() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local sizes = {
+          Handlers.add("a", "A", record("a")),
+          Handlers.append("b", "B", record("b")),
+          Handlers.prepend("c", "C", record("c")),
+          Handlers.add("a", "A2", record("a2")),
+        }
+        Handlers.before("b").add("d", "D", record("d"))
+        Handlers.after("b").add("e", "E", record("e"))
+        Handlers.evaluate({ Action = "A2" }, ENV)
+        return { table.concat(sizes, ","), names(), table.concat(Ran, ",") }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"1,2,3,3\",\n  \"c,a,d,b,e\",\n  \"a2:A2\",\n}\n"
+    );
+}
+
+#[test]
+fn once_and_max_runs_remove_spent_handlers() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"Handlers.once("Ping", record("once"))
+        Handlers.add("twice", "Ping", record("twice"), 2)
+        Handlers.add("always", { Action = "Ping" }, record("always"), "inf")
+        local before = names()
+        for _ = 1, 3 do
+          Handlers.evaluate({ Action = "Ping" }, ENV)
+        end
+        return { before, names(), table.concat(Ran, ",") }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"_once_0,twice,always\",\n  \"always\",\n  \
+         \"once:Ping,twice:Ping,twice:Ping\",\n}\n"
+    );
+}
+
+#[test]
+fn remove_drops_a_handler_by_name() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"Handlers.add("a", "A", record("a"))
+        Handlers.add("b", "B", record("b"))
+        Handlers.remove("a")
+        Handlers.remove("missing")
+        local ok, err = pcall(Handlers.remove, 1)
+        return { names(), ok, tostring(err):match("name MUST be string") ~= nil }"#,
+    );
+    assert_eq!(results, "return {\n  \"b\",\n  false,\n  true,\n}\n");
+}
+
+#[test]
+fn evaluate_follows_pattern_codes_and_default() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"Handlers.add("continues", function() return "continue" end, record("continues"))
+        Handlers.add("skips", function() return 0 end, record("skips"))
+        Handlers.add("passes", function(msg) return msg.Action == "Stop" and -1 or 1 end,
+          record("passes"))
+        Handlers.add("after", "Stop", record("after"))
+        Handlers.add("_default", function() return true end, record("default"))
+        local stopped = Handlers.evaluate({ Action = "Stop" }, ENV)
+        local go = Handlers.evaluate({ Action = "Go" }, ENV)
+        Handlers.add("bad", function() return nil end, record("bad"))
+        local ok, err = pcall(Handlers.evaluate, { Action = "Go" }, ENV)
+        return {
+          stopped,
+          go == nil,
+          table.concat(Ran, ","),
+          ok,
+          tostring(err),
+          (pcall(Handlers.evaluate, "msg", ENV)),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  true,\n  true,\n  \
+         \"continues:Stop,passes:Stop,continues:Go,passes:Go,default:Go,continues:Go,passes:Go\",\n  \
+         false,\n  \"Pattern result is not valid, it MUST be string, number, or boolean\",\n  \
+         false,\n}\n"
+    );
+}
+
+#[test]
+fn resolver_tables_dispatch_on_match_specs() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"Handlers.add("resolved", function() return 1 end, {
+          [{ Action = "A" }] = record("spec-a"),
+          ["B"] = record("spec-b"),
+        })
+        for _, action in ipairs({ "A", "B", "C" }) do
+          Handlers.evaluate({ Action = action }, ENV)
+        end
+        return table.concat(Ran, ",")"#,
+    );
+    assert_eq!(results, "return \"spec-a:A,spec-b:B\"\n");
+}
+
+#[test]
+fn receive_suspends_the_handler_until_the_reply() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local answer
+        Handlers.add("ask", "Ask", function(msg)
+          answer = Handlers.receive({ Action = "Answer" }).Data
+          Ran[#Ran + 1] = "ask done"
+        end)
+        local asking = coroutine.create(Handlers.evaluate)
+        local ok, pattern = coroutine.resume(asking, { Action = "Ask" }, ENV)
+        local waiting = { coroutine.status(asking), pattern.Action, names() }
+        Handlers.evaluate({ Action = "Answer", Data = "42" }, ENV)
+        return { ok, waiting, answer, coroutine.status(asking), names(), table.concat(Ran, ",") }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  true,\n  {\n    \"suspended\",\n    \"Answer\",\n    \"_once_0,ask\",\n  },\n  \
+         \"42\",\n  \"dead\",\n  \"ask\",\n  \"ask done\",\n}\n"
+    );
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::{lua, render};
+
+const SETUP: &str = r#"
+json = require(".json")
+function failure(f, ...)
+  local ok, err = pcall(f, ...)
+  assert(not ok, "expected an error")
+  return tostring(err)
+end
+"#;
+
+#[test]
+fn values_round_trip() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local value = {
+          list = { 1, 2.5, -3, { nested = "x" }, {} },
+          text = "line\nbreak",
+          yes = true,
+          no = false,
+        }
+        local decoded = json.decode(json.encode(value))
+        return {
+          render(decoded) == render(value),
+          math.type(json.decode("1")),
+          math.type(json.decode("1.0")),
+          json.decode("-1.5e2"),
+          json.encode({ 1, "two", false }),
+          json.encode({ key = { 1 } }),
+          json.encode(nil),
+          json.decode("null", json.null) == json.null,
+          json.encode({ a = json.null }),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  true,\n  \"integer\",\n  \"float\",\n  -150.0,\n  \"[1,\\\"two\\\",false]\",\n  \
+         \"{\\\"key\\\":[1]}\",\n  \"null\",\n  true,\n  \"{\\\"a\\\":null}\",\n}\n"
+    );
+}
+
+#[test]
+fn strings_are_escaped_both_ways() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let (encoded, decoded): (String, String) = lua
+        .load(
+            r#"return json.encode("\"\\\b\f\n\r\t\1\127/é"),
+              json.decode([["é😀\/\"\\\n"]])"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(encoded, r#""\"\\\b\f\n\r\t\u0001\u007f/é""#);
+    assert_eq!(decoded, "é😀/\"\\\n");
+}
+
+#[test]
+fn invalid_utf8_is_an_error() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let errors: Vec<String> = lua
+        .load(
+            r#"return {
+              failure(json.encode, "a\255b"),
+              failure(json.encode, { ["\192"] = 1 }),
+              failure(json.encode, { "ok", { "\237\160\128" } }),
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    let expected = [
+        "invalid UTF-8 in string at byte 2",
+        "invalid UTF-8 in string at byte 1",
+        "invalid UTF-8 in string at byte 1",
+    ];
+    for (error, expected) in errors.iter().zip(expected) {
+        assert!(error.contains(expected), "{} lacks {}", error, expected);
+    }
+}
+
+#[test]
+fn nesting_is_limited() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local function nested(n) return string.rep("[", n) .. string.rep("]", n) end
+        return { type(json.decode(nested(513))), failure(json.decode, nested(514)) }"#,
+    );
+    assert!(
+        results.contains("too many nested values at line 1 col 514"),
+        "{}",
+        results
+    );
+    assert!(results.starts_with("return {\n  \"table\","), "{}", results);
+}
+
+#[test]
+fn decode_errors_report_their_position() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let errors: Vec<String> = lua
+        .load(
+            r#"return {
+              failure(json.decode, '{\n  "a": tru\n}'),
+              failure(json.decode, "1 x"),
+              failure(json.decode, "[1,]"),
+              failure(json.decode, '{"a" 1}'),
+              failure(json.decode, '"open'),
+              failure(json.decode, '"\\x"'),
+              failure(json.decode, '"\\ud800"'),
+              failure(json.decode, ""),
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    let expected = [
+        "invalid literal at line 2 col 8",
+        "trailing garbage at line 1 col 3",
+        "unexpected character at line 1 col 4",
+        "expected ':' after key at line 1 col 6",
+        "expected closing quote for string at line 1 col 6",
+        "invalid escape char in string at line 1 col 4",
+        "invalid unicode escape in string at line 1 col 8",
+        "unexpected end of input at line 1 col 1",
+    ];
+    for (error, expected) in errors.iter().zip(expected) {
+        assert!(error.contains(expected), "{} lacks {}", error, expected);
+    }
+}
+
+#[test]
+fn unencodable_values_are_errors() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let errors: Vec<String> = lua
+        .load(
+            r#"local cycle = {}
+            cycle.self = cycle
+            return {
+              failure(json.encode, cycle),
+              failure(json.encode, { [2] = 2 }),
+              failure(json.encode, { 1, key = 2 }),
+              failure(json.encode, print),
+              failure(json.encode, 0 / 0),
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    let expected = [
+        "circular reference",
+        "invalid table: sparse array",
+        "invalid table: mixed or invalid key types",
+        "unexpected type 'function'",
+        "unexpected number value",
+    ];
+    for (error, expected) in errors.iter().zip(expected) {
+        assert!(error.contains(expected), "{} lacks {}", error, expected);
+    }
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::lua;
+use mlua::prelude::*;
+
+#[test]
+fn tags_are_read_as_list_or_map() {
+    let lua = lua();
+    let matches: Vec<f64> = lua
+        .load(
+            r#"local hasAction = require(".handlers-utils").hasMatchingTag("Action", "Ping")
+            return {
+              hasAction({ Tags = { Action = "Ping" } }),
+              hasAction({ Tags = { { name = "Action", value = "Ping" } } }),
+              hasAction({ Tags = { { name = "Action", value = "Pong" } } }),
+              hasAction({}),
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(matches, vec![-1.0, -1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn normalize_lifts_tags_of_either_shape() {
+    let lua = lua();
+    let lifted: Vec<String> = lua
+        .load(
+            r#"local list = ao.normalize({ Tags = { { name = "Action", value = "Ping" } } })
+            local map = ao.normalize({ Tags = { Action = "Pong", Quantity = 5 } })
+            return { list.Action, map.Action, map.Quantity }"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(lifted, vec!["Ping", "Pong", "5"]);
+}
+
+#[test]
+fn malformed_messages_share_one_error() {
+    let lua = lua();
+    let errors: Vec<String> = lua
+        .load(
+            r#"local hasAction = require(".handlers-utils").hasMatchingTag("Action", "Ping")
+            local function failure(fn, ...)
+              local ok, err = pcall(fn, ...)
+              assert(not ok, "expected an error")
+              return tostring(err):match("malformed [^\n]*")
+            end
+            return {
+              failure(hasAction, { Tags = "Action=Ping" }),
+              failure(ao.isTrusted, { From = {} }),
+              failure(ao.normalize, { Tags = { { name = "Action", value = "Ping" }, Extra = "x" } }),
+              failure(ao.init, { Process = { Owner = "OWNER" } }),
+            }"#,
+        )
+        .eval()
+        .unwrap();
+    assert_eq!(
+        errors,
+        vec![
+            "malformed message: Tags must be a table, got string",
+            "malformed message: From must be a string, got table",
+            "malformed message: Tags mixes a list with named entries",
+            "malformed process: Id is missing",
+        ]
+    );
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{lua, render};
+
+#[test]
+fn handler_waiting_for_a_reply_is_kept_until_it_arrives() {
+    let lua = lua();
+    let results = render(
+        &lua,
+        r#"Handlers = require(".handlers")
+        local process = require(".process")
+        local target = "PcDbiJNE7fC4cGlRnvzfxUdzNESjKkiTZlI6gKifhSw"
+        local function message(fields)
+          fields.Target = fields.Target or "PROCESS"
+          fields.Owner = fields.From
+          return fields
+        end
+        Handlers.add("ask", "Ask", function(msg)
+          local reply = ao.send({ Target = target, Action = "Ping" }).receive()
+          msg.reply({ Data = "got " .. reply.Data })
+        end)
+        local asked = process.handle(message({
+          Id = "ASK", From = "OWNER", Tags = { { name = "Action", value = "Ask" } },
+        }), ENV)
+        local waiting = #Handlers.coroutines
+        local answered = process.handle(message({
+          Id = "REPLY", From = target, Data = "pong",
+          Tags = { { name = "X-Reference", value = "1" } },
+        }), ENV)
+        return {
+          #asked.Messages,
+          waiting,
+          answered.Messages[1].Data,
+          #Handlers.coroutines,
+        }"#,
+    );
+    assert_eq!(results, "return {\n  1,\n  1,\n  \"got pong\",\n  0,\n}\n");
+}
@@ -0,0 +1,76 @@
+--- The Handler Utils module is a lightweight Lua utility library designed to provide common functionalities for handling and processing messages within the AOS computer system.
+-- @module handlers-utils
+
+local _utils = { _version = "0.0.2" }
+
+--- Checks if a given message has a tag that matches the specified name and value.
+-- @param name The tag name to check
+-- @param value The value to match for in the tag
+-- @return Returns a function that takes a message and returns whether the message has a matching tag
+function _utils.hasMatchingTag(name, value)
+  assert(type(name) == 'string' and type(value) == 'string', 'invalid arguments: (name : string, value : string)')
+
+  return function (msg)
+    return msg.Tags[name] == value
+  end
+end
+
+--- Checks if a given message has a tag that matches the specified name and one of the specified values.
+-- @param name The tag name to check
+-- @param values The list of values of which one should match
+-- @return Returns a function that takes a message and returns whether the message has a matching tag value
+function _utils.hasMatchingTagOf(name, values)
+  assert(type(name) == 'string' and type(values) == 'table', 'invalid arguments: (name : string, values : string[])')
+  return function (msg)
+    for _, value in ipairs(values) do
+      local patternResult = Handlers.utils.hasMatchingTag(name, value)(msg)
+
+      if patternResult ~= 0 and patternResult ~= false and patternResult ~= "skip" then
+        return patternResult
+      end
+    end
+
+    return 0
+  end
+end
+
+--- Checks if a given message has data that matches the specified value.
+-- @param value The value to match against the message data
+-- @return Returns a function that takes a message and returns whether the message data matches the value
+function _utils.hasMatchingData(value)
+  assert(type(value) == 'string', 'invalid arguments: (value : string)')
+  return function (msg)
+    return msg.Data == value
+  end
+end
+
+--- Given an input, returns a function that takes a message and replies to it.
+-- @param input The content to send back. If a string, sends it as data; if a table, assumes a structure with `Tags`
+-- @return Returns a function that takes a message and replies to it
+function _utils.reply(input)
+  assert(type(input) == 'table' or type(input) == 'string', 'invalid arguments: (input : table or string)')
+  return function (msg)
+    if type(input) == 'string' then
+      msg.reply({ Data = input })
+      return
+    end
+    msg.reply(input)
+  end
+end
+
+--- Inverts the provided pattern's result if it matches, so that it continues execution with the next matching handler.
+-- @param fn The pattern function to continue from
+-- @return Returns a function that executes the pattern matching function and returns `1` (continue), so that the execution of handlers continues.
+function _utils.continue(fn)
+  assert(type(fn) == 'function', 'invalid arguments: (fn : function)')
+  return function (msg)
+    local patternResult = fn(msg)
+
+    if not patternResult or patternResult == 0 or patternResult == "skip" then
+      return patternResult
+    end
+    return 1
+  end
+end
+
+return _utils
@@ -0,0 +1,95 @@
+--- The Stringify module provides utilities for formatting and displaying Lua tables in a more readable manner.
+-- @module stringify
+
+local stringify = { _version = "0.0.1" }
+
+local colors = {
+  red = "\27[31m",
+  green = "\27[32m",
+  blue = "\27[34m",
+  reset = "\27[0m"
+}
+
+--- Checks if a table is a simple array (i.e., an array with consecutive numeric keys starting from 1).
+-- @param tbl The table to check
+-- @return Whether the table is a simple array
+function stringify.isSimpleArray(tbl)
+  local arrayIndex = 1
+  for k, v in pairs(tbl) do
+    if k ~= arrayIndex or (type(v) ~= "number" and type(v) ~= "string") then
+      return false
+    end
+    arrayIndex = arrayIndex + 1
+  end
+  return true
+end
+
+--- Formats a table for display, handling circular references and formatting strings and tables recursively.
+-- @param tbl The table to format
+-- @param indent The indentation level (default is 0)
+-- @param visited A table to track visited tables and detect circular references (optional)
+-- @return A string representation of the table
+function stringify.format(tbl, indent, visited)
+  indent = indent or 0
+  local toIndent = string.rep(" ", indent)
+  local toIndentChild = string.rep(" ", indent + 2)
+
+  local result = {}
+  local isArray = true
+  local arrayIndex = 1
+
+  if stringify.isSimpleArray(tbl) then
+    for _, v in ipairs(tbl) do
+      if type(v) == "string" then
+        v = colors.green .. '"' .. v .. '"' .. colors.reset
+      else
+        v = colors.blue .. tostring(v) .. colors.reset
+      end
+      table.insert(result, v)
+    end
+    return "{ " .. table.concat(result, ", ") .. " }"
+  end
+
+  for k, v in pairs(tbl) do
+    if isArray then
+      if k == arrayIndex then
+        arrayIndex = arrayIndex + 1
+        if type(v) == "table" then
+          v = stringify.format(v, indent + 2)
+        elseif type(v) == "string" then
+          v = colors.green .. '"' .. v .. '"' .. colors.reset
+        else
+          v = colors.blue .. tostring(v) .. colors.reset
+        end
+        table.insert(result, toIndentChild .. v)
+      else
+        isArray = false
+        result = {}
+      end
+    end
+    if not isArray then
+      if type(v) == "table" then
+        visited = visited or {}
+        if visited[v] then
+          return "<circular reference>"
+        end
+        visited[v] = true
+
+        v = stringify.format(v, indent + 2, visited)
+      elseif type(v) == "string" then
+        v = colors.green .. '"' .. v .. '"' .. colors.reset
+      else
+        v = colors.blue .. tostring(v) .. colors.reset
+      end
+      k = colors.red .. k .. colors.reset
+      table.insert(result, toIndentChild .. k .. " = " .. v)
+    end
+  end
+
+  local prefix = isArray and "{\n" or "{\n "
+  local suffix = "\n" .. toIndent .. "}"
+  local separator = isArray and ",\n" or ",\n "
+  return prefix .. table.concat(result, separator) .. suffix
+end
+
+return stringify
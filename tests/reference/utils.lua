@@ -0,0 +1,311 @@
+--- The Utils module provides a collection of utility functions for functional programming in Lua.
+-- @module utils
+
+local utils = { _version = "0.0.5" }
+
+--- Given a pattern, a value, and a message, returns whether there is a pattern match.
+-- @param pattern The pattern to match
+-- @param value The value to check for in the pattern
+-- @param msg The message to check for the pattern
+-- @return Whether there is a pattern match
+utils.matchesPattern = function (pattern, value, msg)
+  -- If the key is not in the message, then it does not match
+  if(not pattern) then
+    return false
+  end
+  -- if the patternMatchSpec is a wildcard, then it always matches
+  if pattern == '_' then
+    return true
+  end
+  -- if the patternMatchSpec is a function, then it is executed on the tag value
+  if type(pattern) == "function" then
+    if pattern(value, msg) then
+      return true
+    else
+      return false
+    end
+  end
+  -- if the patternMatchSpec is a string, check it for special symbols (less `-` alone)
+  -- and exact string match mode
+  if (type(pattern) == 'string') then
+    if string.match(pattern, "[%^%$%(%)%%%.%[%]%*%+%?]") then
+      if string.match(value, pattern) then
+        return true
+      end
+    else
+      if value == pattern then
+        return true
+      end
+    end
+  end
+
+  -- if the pattern is a table, recursively check if any of its sub-patterns match
+  if type(pattern) == 'table' then
+    for _, subPattern in pairs(pattern) do
+      if utils.matchesPattern(subPattern, value, msg) then
+        return true
+      end
+    end
+  end
+
+  return false
+end
+
+--- Given a message and a spec, returns whether there is a spec match.
+-- @param msg The message to check for the spec
+-- @param spec The spec to check for in the message
+-- @return Whether there is a spec match
+utils.matchesSpec = function (msg, spec)
+  if type(spec) == 'function' then
+    return spec(msg)
+  -- If the spec is a table, step through every tag/pattern pair and check if the msg matches
+  -- Supported pattern types:
+  --   - Exact string match
+  --   - Lua gmatch string
+  --   - '_' (wildcard: Message has tag, but can be any value)
+  --   - Function execution on the tag, optionally using the msg as the second argument
+  --   - Table of patterns, where ANY of the sub-patterns matching the tag will result in a match
+  end
+  if type(spec) == 'table' then
+    for key, pattern in pairs(spec) do
+      -- The key can either be in the top level of the 'msg' object
+      -- or in the body table of the msg
+      local msgValue = msg[key]
+      local msgTagValue = msg['Tags'] and msg['Tags'][key]
+      if not msgValue and not msgTagValue then
+        return false
+      end
+      local matchesMsgValue = utils.matchesPattern(pattern, msgValue, msg)
+      local matchesMsgTagValue = utils.matchesPattern(pattern, msgTagValue, msg)
+      if not matchesMsgValue and not matchesMsgTagValue then
+        return false
+      end
+    end
+    return true
+  end
+
+  if type(spec) == 'string' and msg.Action and msg.Action == spec then
+    return true
+  end
+  return false
+end
+
+--- Given a table, returns whether it is an array.
+-- An 'array' is defined as a table with integer keys starting from 1 and
+-- having no gaps between the keys.
+-- @param table The table to check
+-- @return Whether the table is an array
+local function isArray(table)
+  if type(table) == "table" then
+    local maxIndex = 0
+    for k, v in pairs(table) do
+      if type(k) ~= "number" or k <= 0 or math.floor(k) ~= k then
+        return false -- If there's a non-positive-integer key, it's not an array
+      end
+      maxIndex = math.max(maxIndex, k)
+    end
+    -- If the highest numeric index is equal to the number of elements, it's an array
+    return maxIndex == #table
+  end
+  return false
+end
+
+--- Curries a function.
+-- @param fn The function to curry
+-- @param arity The arity of the function
+-- @return The curried function
+utils.curry = function (fn, arity)
+  assert(type(fn) == "function", "function is required as first argument")
+  arity = arity or debug.getinfo(fn, "u").nparams
+  if arity < 2 then return fn end
+
+  return function (...)
+    local args = {...}
+
+    if #args >= arity then
+      return fn(table.unpack(args))
+    else
+      return utils.curry(function (...)
+        return fn(table.unpack(args),  ...)
+      end, arity - #args)
+    end
+  end
+end
+
+--- Concat two Array Tables
+-- @param a The first table
+-- @param b The second table
+-- @return The concatenated table
+utils.concat = utils.curry(function (a, b)
+  assert(type(a) == "table", "first argument should be a table that is an array")
+  assert(type(b) == "table", "second argument should be a table that is an array")
+  assert(isArray(a), "first argument should be a table")
+  assert(isArray(b), "second argument should be a table")
+
+  local result = {}
+  for i = 1, #a do
+    result[#result + 1] = a[i]
+  end
+  for i = 1, #b do
+    result[#result + 1] = b[i]
+  end
+  return result
+end, 2)
+
+--- Applies a function to each element of a table, reducing it to a single value.
+-- @param fn The function to apply
+-- @param initial The initial value
+-- @param t The table to reduce
+-- @return The reduced value
+utils.reduce = utils.curry(function (fn, initial, t)
+  assert(type(fn) == "function", "first argument should be a function that accepts (result, value, key)")
+  assert(type(t) == "table" and isArray(t), "third argument should be a table that is an array")
+  local result = initial
+  for k, v in pairs(t) do
+    if result == nil then
+      result = v
+    else
+      result = fn(result, v, k)
+    end
+  end
+  return result
+end, 3)
+
+--- Applies a function to each element of an array table
+-- @param fn The function to apply
+-- @param data The table to apply the function to
+-- @return The table with the function applied
+utils.map = utils.curry(function (fn, data)
+  assert(type(fn) == "function", "first argument should be a unary function")
+  assert(type(data) == "table" and isArray(data), "second argument should be an Array")
+
+  local function map (result, v, k)
+    result[k] = fn(v, k)
+    return result
+  end
+
+  return utils.reduce(map, {}, data)
+end, 2)
+
+--- Filters an array table based on a predicate function
+-- @param fn The predicate function
+-- @param data The table to filter
+-- @return The filtered table
+utils.filter = utils.curry(function (fn, data)
+  assert(type(fn) == "function", "first argument should be a unary function")
+  assert(type(data) == "table" and isArray(data), "second argument should be an Array")
+
+  local function filter (result, v, _k)
+    if fn(v) then
+      table.insert(result, v)
+    end
+    return result
+  end
+
+  return utils.reduce(filter,{}, data)
+end, 2)
+
+--- Finds the first element in an array table that satisfies a predicate function
+-- @param fn The predicate function
+-- @param t The table to search
+-- @return The first element that satisfies the predicate function
+utils.find = utils.curry(function (fn, t)
+  assert(type(fn) == "function", "first argument should be a unary function")
+  assert(type(t) == "table", "second argument should be a table that is an array")
+  for _, v in pairs(t) do
+    if fn(v) then
+      return v
+    end
+  end
+end, 2)
+
+--- Checks if a property of an object is equal to a value
+-- @param propName The property name
+-- @param value The value to check
+-- @param object The object to check
+-- @return Whether the property is equal to the value
+utils.propEq = utils.curry(function (propName, value, object)
+  assert(type(propName) == "string", "first argument should be a string")
+  -- assert(type(value) == "string", "second argument should be a string")
+  assert(type(object) == "table", "third argument should be a table<object>")
+
+  return object[propName] == value
+end, 3)
+
+--- Reverses an array table
+-- @param data The table to reverse
+-- @return The reversed table
+utils.reverse = function (data)
+  assert(type(data) == "table", "argument needs to be a table that is an array")
+  return utils.reduce(
+    function (result, v, i)
+      result[#data - i + 1] = v
+      return result
+    end,
+    {},
+    data
+  )
+end
+
+--- Composes a series of functions into a single function
+-- @param ... The functions to compose
+-- @return The composed function
+utils.compose = utils.curry(function (...)
+  local mutations = utils.reverse({...})
+
+  return function (v)
+    local result = v
+    for _, fn in pairs(mutations) do
+      assert(type(fn) == "function", "each argument needs to be a function")
+      result = fn(result)
+    end
+    return result
+  end
+end, 2)
+
+--- Returns the property value that belongs to the property name provided from an object
+-- @param propName The property name
+-- @param object The object to check
+-- @return The property value
+utils.prop = utils.curry(function (propName, object)
+  return object[propName]
+end, 2)
+
+--- Checks if an array table includes a value
+-- @param val The value to check
+-- @param t The table to check
+-- @return Whether the value is in the table
+utils.includes = utils.curry(function (val, t)
+  assert(type(t) == "table", "argument needs to be a table")
+  assert(isArray(t), "argument should be a table that is an array")
+  return utils.find(function (v) return v == val end, t) ~= nil
+end, 2)
+
+--- Returns the keys of a table
+-- @param t The table to get the keys from
+-- @return The keys of the table
+utils.keys = function (t)
+  assert(type(t) == "table", "argument needs to be a table")
+  local keys = {}
+  for key in pairs(t) do
+    table.insert(keys, key)
+  end
+  return keys
+end
+
+--- Returns the values of a table
+-- @param t The table to get the values from
+-- @return The values of the table
+utils.values = function (t)
+  assert(type(t) == "table", "argument needs to be a table")
+  local values = {}
+  for _, value in pairs(t) do
+    table.insert(values, value)
+  end
+  return values
+end
+
+--- Convert a message's tags to a table of key-value pairs
+utils.isArray = isArray
+
+return utils
@@ -0,0 +1,103 @@
+mod common;
+
+use common::{lua, render};
+
+const SETUP: &str = r#"
+state = require(".state")
+function ids()
+  local ids = {}
+  for _, msg in ipairs(Inbox) do ids[#ids + 1] = msg.Id end
+  return table.concat(ids, ",")
+end
+"#;
+
+#[test]
+fn inbox_evicts_oldest_messages_past_the_max() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"MAX_INBOX_SIZE = 3
+        for i = 1, 5 do state.insertInbox({ Id = tostring(i) }) end
+        local kept = ids()
+        MAX_INBOX_SIZE = 1
+        state.insertInbox({ Id = "6" })
+        local shrunk = ids()
+        local dropped, emptied = state.reset(), #Inbox
+        MAX_INBOX_SIZE = -1
+        local ok, err = pcall(state.insertInbox, { Id = "7" })
+        return {
+          kept,
+          shrunk,
+          dropped,
+          emptied,
+          ok,
+          tostring(err):find("MAX_INBOX_SIZE must not be negative", 1, true) ~= nil,
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"3,4,5\",\n  \"6\",\n  1,\n  0,\n  false,\n  true,\n}\n"
+    );
+}
+
+#[test]
+fn globals_survive_a_reload() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"state.initializeState({ Id = "FIRST" }, ENV)
+        local defaults = { Name, Owner, MAX_INBOX_SIZE, Initialized, Seeded }
+        Name, MAX_INBOX_SIZE = "custom", 50
+        state.insertInbox({ Id = "KEPT" })
+        local chance = require(".chance")
+        chance.integer(1, 1000000)
+
+        package.loaded[".state"] = nil
+        state = require(".state")
+        local env = { Process = { Id = "PROCESS", Owner = "SOMEONE-ELSE", Tags = {} } }
+        state.initializeState({ Id = "SECOND", Timestamp = "1" }, env)
+        local continued = chance.integer(1, 1000000)
+        -- Replay the first seed: the draw after the reload must be its second one
+        chance.seedFromMessage({ Id = "FIRST" })
+        chance.integer(1, 1000000)
+        return {
+          defaults,
+          { Name, Owner, MAX_INBOX_SIZE, ids() },
+          continued == chance.integer(1, 1000000),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  {\n    \"aos\",\n    \"OWNER\",\n    10000,\n    true,\n    true,\n  },\n  \
+         {\n    \"custom\",\n    \"OWNER\",\n    50,\n    \"KEPT\",\n  },\n  true,\n}\n"
+    );
+}
+
+#[test]
+fn check_slice_tells_evals_from_messages() {
+    let lua = lua();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"Owner = "OWNER"
+        local function failure(msg)
+          return not pcall(state.checkSlice, msg)
+        end
+        return {
+          state.checkSlice({ Action = "Eval", From = "OWNER" }),
+          state.checkSlice({ Action = "Eval", From = "STRANGER" }),
+          state.checkSlice({ Action = "Ping", From = "OWNER" }),
+          state.checkSlice({ Action = "Eval" }),
+          failure({ Action = { "Eval" }, From = "OWNER" }),
+          failure({ Action = "Eval", From = print }),
+          failure(nil),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  \"eval\",\n  \"message\",\n  \"message\",\n  \"message\",\n  true,\n  \
+         true,\n  true,\n}\n"
+    );
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{assert_golden, lua, render};
+
+#[test]
+fn matches_spec_cases() {
+    let lua = lua();
+    let results = render(
+        &lua,
+        r#"local utils = require(".utils")
+        local msg = { Action = "Transfer", Quantity = "100", Tags = { Recipient = "BOB" } }
+        return {
+          utils.matchesSpec(msg, "Transfer"),
+          utils.matchesSpec(msg, "Balance"),
+          utils.matchesSpec(msg, { Action = "Transfer" }),
+          utils.matchesSpec(msg, { Quantity = "%d+" }),
+          utils.matchesSpec(msg, { Quantity = "^%a+$" }),
+          utils.matchesSpec(msg, { Recipient = "BOB" }),
+          utils.matchesSpec(msg, { Action = "_" }),
+          utils.matchesSpec(msg, { Missing = "_" }),
+          utils.matchesSpec(msg, { Action = { "Balance", "Transfer" } }),
+          utils.matchesSpec(msg, { Quantity = function(v) return tonumber(v) > 50 end }),
+          utils.matchesSpec(msg, function(m) return m.Quantity == "1" end),
+        }"#,
+    );
+    assert_golden("matches_spec", &results);
+}
+
+#[test]
+fn matches_spec_skips_malformed_fields() {
+    let lua = lua();
+    let results = render(
+        &lua,
+        r#"local utils = require(".utils")
+        local msg = {
+          From = {},
+          Action = 5,
+          Tags = { { name = "Action" }, "junk", { name = "Recipient", value = "BOB" } },
+        }
+        return {
+          utils.matchesSpec(msg, { Recipient = "BOB" }),
+          utils.matchesSpec(msg, { Action = "_" }),
+          utils.matchesSpec(msg, { From = "ALICE" }),
+          utils.matchesSpec(msg, "5"),
+          utils.matchesSpec({ Tags = "Action=Ping" }, { Action = "Ping" }),
+        }"#,
+    );
+    assert_eq!(
+        results,
+        "return {\n  true,\n  true,\n  false,\n  false,\n  false,\n}\n"
+    );
+}
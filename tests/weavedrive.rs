@@ -0,0 +1,116 @@
+mod common;
+
+use common::{lua, render};
+use std::path::PathBuf;
+
+/// Stubs the WeaveDrive mount with `/data`, `/tx` and `/block` files in a temporary
+/// directory, served to the module through `io.open`.
+fn mount(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ao-rust-{}-{}", name, std::process::id()));
+    for dir in ["data", "tx", "block"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    std::fs::write(root.join("data/TX1"), "hello\0weave").unwrap();
+    std::fs::write(
+        root.join("tx/TX1"),
+        r#"{"id":"TX1","owner":{"address":"ADDR"},"tags":[{"name":"Type","value":"Test"}]}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("tx/ITEM1"),
+        r#"{"id":"ITEM1","bundledIn":{"id":"TX1"}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("block/1000"),
+        r#"{"height":1000,"indep_hash":"HASH"}"#,
+    )
+    .unwrap();
+    root
+}
+
+const SETUP: &str = r#"
+local open = io.open
+io.open = function(path, mode) return open(ROOT .. path, mode) end
+weavedrive = require(".weavedrive")
+table.insert(ao.env.Process.Tags, { name = "Extension", value = "WeaveDrive" })
+"#;
+
+#[test]
+fn reads_headers_and_data_from_the_mount() {
+    let root = mount("weavedrive-read");
+    let lua = lua();
+    lua.globals().set("ROOT", root.to_str().unwrap()).unwrap();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local tx = weavedrive.getTx("TX1")
+        return {
+          weavedrive.getData("TX1") == "hello\0weave",
+          tx.owner.address,
+          tx.tags[1].value,
+          weavedrive.getBlock(1000).indep_hash,
+          weavedrive.getBlock("1000").height,
+          weavedrive.getDataItem("ITEM1").bundledIn.id,
+        }"#,
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(
+        results,
+        "return {\n  true,\n  \"ADDR\",\n  \"Test\",\n  \"HASH\",\n  1000,\n  \"TX1\",\n}\n"
+    );
+}
+
+#[test]
+fn missing_files_return_nil_and_a_message() {
+    let root = mount("weavedrive-missing");
+    let lua = lua();
+    lua.globals().set("ROOT", root.to_str().unwrap()).unwrap();
+    lua.load(SETUP).exec().unwrap();
+    let results = render(
+        &lua,
+        r#"local function lookup(f, key)
+          local value, err = f(key)
+          return { value == nil, err }
+        end
+        return {
+          lookup(weavedrive.getData, "NOPE"),
+          lookup(weavedrive.getTx, "NOPE"),
+          lookup(weavedrive.getBlock, 5),
+          lookup(weavedrive.getDataItem, "NOPE"),
+        }"#,
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(
+        results,
+        "return {\n  {\n    true,\n    \"Data not found!\",\n  },\n  \
+         {\n    true,\n    \"Transaction not found!\",\n  },\n  \
+         {\n    true,\n    \"Block header not found!\",\n  },\n  \
+         {\n    true,\n    \"Data item not found!\",\n  },\n}\n"
+    );
+}
+
+#[test]
+fn lookups_require_the_extension_and_valid_heights() {
+    let lua = lua();
+    let (height, disabled): (String, String) = lua
+        .load(
+            r#"local weavedrive = require(".weavedrive")
+            local _, disabled = pcall(weavedrive.getData, "TX1")
+            table.insert(ao.env.Process.Tags, { name = "Extension", value = "WeaveDrive" })
+            local _, height = pcall(weavedrive.getBlock, -1)
+            return tostring(height), tostring(disabled)"#,
+        )
+        .eval()
+        .unwrap();
+    assert!(
+        height.contains("block height must be a non-negative integer"),
+        "{}",
+        height
+    );
+    assert!(
+        disabled.contains("WeaveDrive is not enabled for this process"),
+        "{}",
+        disabled
+    );
+}